@@ -0,0 +1,175 @@
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::Path};
+
+use crate::findings::Finding;
+
+/// File name of the baseline that is searched for in the current and parent dirs.
+pub const BASELINE_FILE_NAME: &str = ".masklint-baseline.json";
+
+/// Identifies a finding independent of where exactly it is in the script, so
+/// it still matches after unrelated lines were added or removed.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Fingerprint {
+    /// The label of the maskfile when several are linted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maskfile: Option<String>,
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    pub message: String,
+}
+
+impl Fingerprint {
+    pub fn new(
+        maskfile: Option<&str>,
+        command: &str,
+        finding: &Finding,
+        script_path: &Path,
+    ) -> Self {
+        Fingerprint {
+            maskfile: maskfile.map(str::to_string),
+            command: command.to_string(),
+            rule: finding.rule.clone(),
+            message: normalize_message(&finding.message, script_path),
+        }
+    }
+}
+
+/// Findings that already existed when the baseline was written and are not
+/// reported anymore, with how often they occurred.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(from = "BaselineFile", into = "BaselineFile")]
+pub struct Baseline {
+    pub findings: BTreeMap<Fingerprint, u32>,
+}
+
+/// The baseline as it's written, a list since the fingerprints can't be keys
+/// in JSON.
+#[derive(Serialize, Deserialize)]
+struct BaselineFile {
+    findings: Vec<BaselineEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BaselineEntry {
+    #[serde(flatten)]
+    fingerprint: Fingerprint,
+    /// Older baselines have no counts and every finding once.
+    #[serde(default = "one", skip_serializing_if = "is_one")]
+    count: u32,
+}
+
+fn one() -> u32 {
+    1
+}
+
+fn is_one(count: &u32) -> bool {
+    *count == 1
+}
+
+impl From<BaselineFile> for Baseline {
+    fn from(file: BaselineFile) -> Self {
+        let mut findings = BTreeMap::new();
+        for entry in file.findings {
+            *findings.entry(entry.fingerprint).or_default() += entry.count;
+        }
+        Baseline { findings }
+    }
+}
+
+impl From<Baseline> for BaselineFile {
+    fn from(baseline: Baseline) -> Self {
+        let findings = baseline
+            .findings
+            .into_iter()
+            .map(|(fingerprint, count)| BaselineEntry { fingerprint, count })
+            .collect();
+        BaselineFile { findings }
+    }
+}
+
+impl Baseline {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("failed to read baseline {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("invalid baseline {}", path.display()))
+    }
+
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)? + "\n")
+    }
+
+    /// How often the finding occurred when the baseline was written, so only
+    /// as many occurrences are suppressed.
+    pub fn count(&self, fingerprint: &Fingerprint) -> u32 {
+        self.findings.get(fingerprint).copied().unwrap_or_default()
+    }
+}
+
+/// Removes everything from a message that changes between runs or when the
+/// script is edited: the path of the dumped script, numbers like line numbers
+/// and whitespace.
+fn normalize_message(message: &str, script_path: &Path) -> String {
+    let mut message = message.replace(&script_path.display().to_string(), "");
+    if let Some(file_name) = script_path.file_name() {
+        message = message.replace(&*file_name.to_string_lossy(), "");
+    }
+    let mut normalized = String::new();
+    let mut last = None;
+    for c in message.chars() {
+        let c = match c {
+            c if c.is_ascii_digit() => '#',
+            c if c.is_whitespace() => ' ',
+            c => c,
+        };
+        // collapse runs of digits and whitespace
+        if matches!(c, '#' | ' ') && last == Some(c) {
+            continue;
+        }
+        normalized.push(c);
+        last = Some(c);
+    }
+    normalized.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("Statement ends with a semicolon", "Statement ends with a semicolon")]
+    #[case("/tmp/.tmpA1b2/build.sh: line 12: syntax error", ": line #: syntax error")]
+    #[case("build.sh:3:10:  unexpected\ntoken", ":#:#: unexpected token")]
+    fn test_normalize_message(#[case] message: &str, #[case] expected: &str) {
+        let script_path = Path::new("/tmp/.tmpA1b2/build.sh");
+        assert_eq!(normalize_message(message, script_path), expected);
+    }
+
+    #[test]
+    fn test_counts() {
+        let fingerprint = |maskfile: Option<&str>| Fingerprint {
+            maskfile: maskfile.map(str::to_string),
+            command: "build".to_string(),
+            rule: Some("SC2086".to_string()),
+            message: "Double quote to prevent globbing".to_string(),
+        };
+        let baseline = Baseline {
+            findings: BTreeMap::from([(fingerprint(None), 2), (fingerprint(Some("docs")), 1)]),
+        };
+        let json = baseline.to_json().unwrap();
+        assert_eq!(json.matches("\"count\": 2").count(), 1);
+        let loaded: Baseline = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.count(&fingerprint(None)), 2);
+        assert_eq!(loaded.count(&fingerprint(Some("docs"))), 1);
+        assert_eq!(loaded.count(&fingerprint(Some("other"))), 0);
+
+        // baselines without counts and maskfiles still load
+        let old = r#"{"findings": [{"command": "build", "rule": "SC2086",
+            "message": "Double quote to prevent globbing"}]}"#;
+        let loaded: Baseline = serde_json::from_str(old).unwrap();
+        assert_eq!(loaded.count(&fingerprint(None)), 1);
+    }
+}
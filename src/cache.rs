@@ -0,0 +1,190 @@
+use log::debug;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::handlers::{LanguageHandler, LintResult, LintResultType, RunSettings};
+
+/// Caches the raw linter results by the script content and the linter
+/// versions, so unchanged scripts don't need to be linted again.
+#[derive(Debug, Clone)]
+pub struct Cache {
+    dir: PathBuf,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedResult {
+    result_type: LintResultType,
+    message: String,
+}
+
+impl Cache {
+    pub fn new(dir: PathBuf) -> Self {
+        Cache { dir }
+    }
+
+    /// The masklint dir in the user's cache dir, e.g. ~/.cache/masklint.
+    pub fn default_dir() -> Option<PathBuf> {
+        dirs::cache_dir().map(|dir| dir.join("masklint"))
+    }
+
+    /// Returns the result of a previous run on the same script with the same
+    /// settings, if any.
+    pub fn get(
+        &self,
+        handler: &dyn LanguageHandler,
+        settings: &RunSettings,
+        file_name: &str,
+        content: &str,
+    ) -> Option<LintResult> {
+        let result = read_entry(&self.entry(handler, settings, file_name, content))?;
+        debug!("using cached result of {handler} for {file_name}");
+        Some(result)
+    }
+
+    pub fn put(
+        &self,
+        handler: &dyn LanguageHandler,
+        settings: &RunSettings,
+        file_name: &str,
+        content: &str,
+        result: &LintResult,
+    ) {
+        let entry = self.entry(handler, settings, file_name, content);
+        let cached = CachedResult {
+            result_type: result.result_type.clone(),
+            message: result.message.clone(),
+        };
+        if let Err(e) = write_entry(&entry, &cached) {
+            debug!("failed to write cache entry {}: {e}", entry.display());
+        }
+    }
+
+    fn entry(
+        &self,
+        handler: &dyn LanguageHandler,
+        settings: &RunSettings,
+        file_name: &str,
+        content: &str,
+    ) -> PathBuf {
+        self.dir.join(format!("{}.json", self.key(handler, settings, file_name, content)))
+    }
+
+    fn key(
+        &self,
+        handler: &dyn LanguageHandler,
+        settings: &RunSettings,
+        file_name: &str,
+        content: &str,
+    ) -> String {
+        let mut hasher = Sha256::new();
+        // the parsing of the output might change between masklint versions
+        hasher.update(env!("CARGO_PKG_VERSION"));
+        // includes the settings of the handler, e.g. the sql dialect
+        hasher.update(format!("{handler:?}"));
+        // the file name shows up in some of the linter output
+        hasher.update(file_name);
+        hasher.update(content);
+        hasher.update(settings.describe(handler));
+        for executable in handler.executables() {
+            // optional linters might not be installed, installing them later
+            // changes the key as well
+            let version = settings.version_output(executable);
+            hasher.update(version.as_deref().unwrap_or("missing"));
+        }
+        hasher.finalize().iter().map(|b| format!("{b:02x}")).collect()
+    }
+}
+
+fn read_entry(path: &Path) -> Option<LintResult> {
+    let content = fs::read_to_string(path).ok()?;
+    let cached: CachedResult = serde_json::from_str(&content).ok()?;
+    Some(match cached.result_type {
+        LintResultType::Warning => LintResult::warning(cached.message),
+        LintResultType::Findings => LintResult::findings(cached.message),
+    })
+}
+
+fn write_entry(path: &Path, cached: &CachedResult) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_string(cached)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::{Batch, ConfigSource, Container, Environment, Ruff, Wrapper};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_cache_roundtrip() {
+        let cache_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let script_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let script_path = script_dir.path().join("build.bat");
+        let cache = Cache::new(cache_dir.path().to_path_buf());
+
+        fs::write(&script_path, "goto missing").unwrap();
+        let settings = RunSettings::default();
+        let result = Batch.execute(&script_path, &settings).unwrap();
+        assert!(cache.get(&Batch, &settings, "build.bat", "goto missing").is_none());
+        cache.put(&Batch, &settings, "build.bat", "goto missing", &result);
+        assert_eq!(fs::read_dir(cache_dir.path()).unwrap().count(), 1);
+
+        let cached = cache.get(&Batch, &settings, "build.bat", "goto missing").unwrap();
+        assert_eq!(cached.message, result.message);
+        assert!(cache.get(&Batch, &settings, "build.bat", "echo fine").is_none());
+        assert!(cache.get(&Batch, &settings, "other.bat", "goto missing").is_none());
+    }
+
+    #[test]
+    fn test_cache_key_settings() {
+        let cache_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let cache = Cache::new(cache_dir.path().to_path_buf());
+        let key = |settings: &RunSettings| cache.key(&Batch, settings, "build.bat", "echo");
+        let default_key = key(&RunSettings::default());
+        let wrapper = Wrapper { executable: "batch", command: vec!["wrapped".to_string()] };
+        assert_ne!(default_key, key(&RunSettings { wrapper: Some(wrapper), ..Default::default() }));
+        let container = Container { image: "batch:2".to_string(), mount: "/tmp".into() };
+        let settings = RunSettings { container: Some(container), ..Default::default() };
+        assert_ne!(default_key, key(&settings));
+        let environment = Environment {
+            vars: BTreeMap::from([("RUBOCOP_OPTS".to_string(), "--lint".to_string())]),
+            ..Environment::default()
+        };
+        let settings = RunSettings { environment: Some(environment), ..Default::default() };
+        assert_ne!(default_key, key(&settings));
+        assert_eq!(default_key, key(&RunSettings::default()));
+    }
+
+    #[test]
+    fn test_cache_key_config() {
+        let cache_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let project = tempfile::tempdir().expect("Failed to create temp dir");
+        let cache = Cache::new(cache_dir.path().to_path_buf());
+        let key = |config_source| {
+            let settings = RunSettings { config_source, ..Default::default() };
+            cache.key(&Ruff, &settings, "build.py", "print()")
+        };
+        let project_source = || Some(ConfigSource::Project(project.path().to_path_buf()));
+
+        let defaults = key(project_source());
+        assert_ne!(defaults, key(Some(ConfigSource::Isolated)));
+        fs::write(project.path().join("ruff.toml"), "line-length = 100\n").unwrap();
+        let configured = key(project_source());
+        assert_ne!(defaults, configured);
+        fs::write(project.path().join("ruff.toml"), "line-length = 120\n").unwrap();
+        assert_ne!(configured, key(project_source()));
+        // the batch linter doesn't take any config
+        let batch_key = |config_source| {
+            let settings = RunSettings { config_source, ..Default::default() };
+            cache.key(&Batch, &settings, "build.bat", "echo")
+        };
+        assert_eq!(batch_key(project_source()), batch_key(Some(ConfigSource::Isolated)));
+    }
+}
@@ -0,0 +1,61 @@
+use clap::ValueEnum;
+use owo_colors::{OwoColorize, Stream, Style};
+use std::{env, fmt::Display};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Colorize if the output is a terminal, respects NO_COLOR and CLICOLOR_FORCE
+    Auto,
+    Always,
+    Never,
+}
+
+/// Decides globally whether the output is colorized. In auto mode the
+/// environment variables take precedence over the terminal detection.
+pub fn init(choice: ColorChoice) {
+    let enabled = match choice {
+        ColorChoice::Always => Some(true),
+        ColorChoice::Never => Some(false),
+        ColorChoice::Auto => from_env(env::var("NO_COLOR").ok(), env::var("CLICOLOR_FORCE").ok()),
+    };
+    if let Some(enabled) = enabled {
+        owo_colors::set_override(enabled);
+    }
+}
+
+/// See https://no-color.org and https://bixense.com/clicolors.
+fn from_env(no_color: Option<String>, clicolor_force: Option<String>) -> Option<bool> {
+    if no_color.is_some_and(|v| !v.is_empty()) {
+        return Some(false);
+    }
+    if clicolor_force.is_some_and(|v| !v.is_empty() && v != "0") {
+        return Some(true);
+    }
+    None
+}
+
+/// Applies the style if colors are enabled for the stream.
+pub fn paint(text: impl Display, stream: Stream, style: Style) -> String {
+    text.if_supports_color(stream, |t| t.style(style)).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(None, None, None)]
+    #[case(Some("1"), Some("1"), Some(false))]
+    #[case(Some(""), None, None)]
+    #[case(None, Some("1"), Some(true))]
+    #[case(None, Some("0"), None)]
+    fn test_from_env(
+        #[case] no_color: Option<&str>,
+        #[case] clicolor_force: Option<&str>,
+        #[case] expected: Option<bool>,
+    ) {
+        let to_string = |v: Option<&str>| v.map(str::to_string);
+        assert_eq!(from_env(to_string(no_color), to_string(clicolor_force)), expected);
+    }
+}
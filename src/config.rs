@@ -0,0 +1,199 @@
+use anyhow::Context;
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// File name of the config that is searched for in the current and parent dirs.
+pub const CONFIG_FILE_NAME: &str = ".masklint.toml";
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Skips linters that are not installed instead of aborting the run.
+    pub allow_missing_linters: bool,
+    /// Lints with the linters' defaults instead of the project's config.
+    pub isolated: bool,
+    /// Only warns about linters that are older than their `min_version`
+    /// instead of aborting the run.
+    pub allow_outdated_linters: bool,
+    /// Runs the linters without their servers, same as --no-daemon.
+    pub no_daemon: bool,
+    /// Checks the syntax of the yaml, json and toml blocks, same as --include-data-blocks.
+    pub include_data_blocks: bool,
+    pub zsh: ZshConfig,
+    pub javascript: JavaScriptConfig,
+    pub typescript: TypeScriptConfig,
+    pub sql: SqlConfig,
+    pub shell: ShellConfig,
+    pub env: EnvConfig,
+    /// Settings per linter, keyed by the linter name, e.g. "shellcheck".
+    pub linters: BTreeMap<String, LinterConfig>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ZshConfig {
+    /// Additionally runs shellcheck in bash mode on zsh scripts. This is only
+    /// best effort since a lot of zsh features are not valid bash.
+    pub shellcheck: bool,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JavaScriptConfig {
+    pub linter: JavaScriptLinter,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JavaScriptLinter {
+    /// Works without any config, so it's used by default.
+    #[default]
+    Biome,
+    Eslint,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TypeScriptConfig {
+    /// Additionally runs `deno check` to report type errors.
+    pub check: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SqlConfig {
+    /// The sqlfluff dialect, e.g. "postgres" or "mysql".
+    pub dialect: String,
+}
+
+impl Default for SqlConfig {
+    fn default() -> Self {
+        SqlConfig { dialect: "ansi".to_string() }
+    }
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LinterConfig {
+    /// Turns the linter on or off, by default only the main linter of each
+    /// language is run.
+    pub enabled: Option<bool>,
+    /// Seconds after which the linter is killed, overrides `--timeout`.
+    pub timeout: Option<u64>,
+    /// The docker image that is used with `--runner docker`.
+    pub image: Option<String>,
+    /// Runs the linter's main executable with this command instead, e.g.
+    /// `["bundle", "exec", "rubocop"]`.
+    pub command: Option<Vec<String>>,
+    /// The oldest version that may be used, e.g. "0.4", since the findings
+    /// differ between versions.
+    pub min_version: Option<String>,
+    /// Variables that are set for the linter, on top of the ones in `[env]`.
+    pub env: BTreeMap<String, String>,
+    /// Runs the linter in this dir instead of the project dir.
+    pub working_dir: Option<PathBuf>,
+}
+
+/// The environment of the linters, which otherwise inherit the one masklint
+/// is run with, so the results don't depend on the caller's shell.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EnvConfig {
+    /// Only these variables are passed on to the linters, besides PATH.
+    pub allow: Option<Vec<String>>,
+    /// Variables that are set for all linters, e.g. `NO_COLOR`.
+    pub vars: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShellConfig {
+    /// Variables that are always available when mask runs a script, so shell
+    /// linters don't report them as unassigned.
+    pub runtime_variables: Vec<String>,
+    /// Reports lowercase variables in shell scripts that are neither assigned
+    /// nor an argument or option (MF007). Off by default since shellcheck
+    /// reports most of them for bash as well.
+    pub undeclared_variables: bool,
+}
+
+impl Default for ShellConfig {
+    fn default() -> Self {
+        // documented in https://github.com/jacobdeichert/mask#environment-variable-utilities
+        ShellConfig {
+            runtime_variables: vec!["MASK".to_string(), "MASKFILE_DIR".to_string()],
+            undeclared_variables: false,
+        }
+    }
+}
+
+impl Config {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("failed to read config {}", path.display()))?;
+        let mut config: Config = toml::from_str(&content)
+            .with_context(|| format!("invalid config {}", path.display()))?;
+        // relative dirs are meant relative to the config, wherever masklint is run
+        let config_dir = path.parent().unwrap_or(Path::new(""));
+        for linter in config.linters.values_mut() {
+            if let Some(dir) = linter.working_dir.as_mut().filter(|d| d.is_relative()) {
+                *dir = config_dir.join(&*dir);
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn linter_enabled(&self, linter: &str, default: bool) -> bool {
+        self.linters.get(linter).and_then(|l| l.enabled).unwrap_or(default)
+    }
+
+    pub fn linter_timeout(&self, linter: &str) -> Option<Duration> {
+        self.linters.get(linter).and_then(|l| l.timeout).map(Duration::from_secs)
+    }
+
+    pub fn linter_image(&self, linter: &str) -> Option<&str> {
+        self.linters.get(linter).and_then(|l| l.image.as_deref())
+    }
+
+    pub fn linter_min_version(&self, linter: &str) -> Option<&str> {
+        self.linters.get(linter).and_then(|l| l.min_version.as_deref())
+    }
+
+    /// The variables of `[env]` with the ones of the linter taking precedence.
+    pub fn linter_env(&self, linter: &str) -> BTreeMap<String, String> {
+        let mut vars = self.env.vars.clone();
+        if let Some(config) = self.linters.get(linter) {
+            vars.extend(config.env.clone());
+        }
+        vars
+    }
+
+    pub fn linter_working_dir(&self, linter: &str) -> Option<&Path> {
+        self.linters.get(linter).and_then(|l| l.working_dir.as_deref())
+    }
+
+    pub fn linter_command(&self, linter: &str) -> Option<&[String]> {
+        self.linters.get(linter).and_then(|l| l.command.as_deref()).filter(|c| !c.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_working_dir() {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let path = dir.path().join(CONFIG_FILE_NAME);
+        let config = "[linters.ruff]\nworking_dir = \"scripts\"\n\n[linters.rubocop]\nworking_dir = \"/tmp\"\n";
+        fs::write(&path, config).unwrap();
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.linter_working_dir("ruff"), Some(dir.path().join("scripts").as_path()));
+        assert_eq!(config.linter_working_dir("rubocop"), Some(Path::new("/tmp")));
+    }
+}
@@ -0,0 +1,139 @@
+use serde::Deserialize;
+
+use crate::{
+    fences::{closes_fence, opening_fence},
+    findings::{Finding, Severity},
+};
+
+/// Name of the checks of the yaml, json and toml blocks, which are only run
+/// with `--include-data-blocks`.
+pub const LINTER: &str = "data";
+
+/// Parses every yaml, json and toml code block in the maskfile and returns
+/// the syntax errors with 1-based lines in the maskfile.
+pub fn check(content: &str) -> Vec<Finding> {
+    let mut findings = vec![];
+    // the fence, the language and the 1-based line of the opening fence
+    let mut open_fence: Option<(char, String, usize)> = None;
+    let mut source = String::new();
+    for (i, raw_line) in content.lines().enumerate() {
+        let line = raw_line.trim();
+        if let Some((fence, language, start)) = &open_fence {
+            if !closes_fence(line, *fence) {
+                source.push_str(raw_line);
+                source.push('\n');
+                continue;
+            }
+            findings.extend(check_block(language, &source, *start));
+            open_fence = None;
+            source.clear();
+            continue;
+        }
+        if let Some(fence) = opening_fence(line) {
+            let language = line.trim_start_matches(fence).trim().to_lowercase();
+            open_fence = Some((fence, language, i + 1));
+        }
+    }
+    // an unclosed code block runs until the end of the file
+    if let Some((_, language, start)) = &open_fence {
+        findings.extend(check_block(language, &source, *start));
+    }
+    findings
+}
+
+/// The lines of the error are relative to the block, which starts in the
+/// line after the opening fence.
+fn check_block(language: &str, source: &str, start: usize) -> Option<Finding> {
+    let (line, column, message) = match language {
+        "yaml" | "yml" => {
+            // a block can hold multiple documents separated by ---
+            let error = serde_yaml::Deserializer::from_str(source)
+                .find_map(|document| serde_yaml::Value::deserialize(document).err())?;
+            let location = error.location();
+            (location.as_ref().map(|l| l.line()), location.map(|l| l.column()), error.to_string())
+        }
+        "json" => {
+            let error = serde_json::from_str::<serde_json::Value>(source).err()?;
+            (Some(error.line()), Some(error.column()), error.to_string())
+        }
+        "toml" => {
+            let error = toml::from_str::<toml::Table>(source).err()?;
+            let offset = error.span().map(|span| span.start);
+            let (line, column) = offset.map(|o| position(source, o)).unzip();
+            (line, column, error.message().to_string())
+        }
+        _ => return None,
+    };
+    Some(Finding {
+        line: Some(start + line.unwrap_or(1)),
+        column,
+        rule: None,
+        severity: Severity::Error,
+        message: format!("invalid {language}: {}", first_line(&message)),
+    })
+}
+
+/// The 1-based line and column of the byte offset.
+fn position(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+    (line, column)
+}
+
+/// Drops the source and the position in the block that some parsers add to
+/// their messages, the position in the maskfile is shown instead.
+fn first_line(message: &str) -> &str {
+    let line = message.lines().next().unwrap_or_default();
+    line.split(" at line ").next().unwrap_or_default().trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let maskfile = "# Tasks
+
+## deploy
+
+```yaml
+replicas: 2
+  image: app
+```
+
+```json
+{\"a\": 1,}
+```
+
+```toml
+[server]
+port =
+```
+
+```yaml
+a: 1
+---
+b: [2
+```
+
+```json
+{\"valid\": true}
+```
+
+```bash
+echo {
+```
+";
+        let findings = check(maskfile);
+        let positions: Vec<(Option<usize>, Option<usize>)> =
+            findings.iter().map(|f| (f.line, f.column)).collect();
+        assert_eq!(
+            positions,
+            [(Some(7), Some(8)), (Some(11), Some(9)), (Some(16), Some(7)), (Some(23), Some(1))]
+        );
+        let messages: Vec<&str> = findings.iter().map(|f| f.message.as_str()).collect();
+        assert_eq!(messages[1], "invalid json: trailing comma");
+    }
+}
@@ -0,0 +1,313 @@
+use anyhow::{anyhow, Context};
+use clap::ValueEnum;
+use globset::{Glob, GlobSetBuilder};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Component, Path, PathBuf},
+    process::Command,
+};
+
+use crate::{handlers::parse_executor, ExtractedScript};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Layout {
+    /// All scripts in one dir, e.g. services_api_build.sh
+    #[default]
+    Flat,
+    /// A dir per parent command, e.g. services/api/build.sh
+    Nested,
+}
+
+/// How the scripts are written by the dump command.
+#[derive(Debug, Default, Clone)]
+pub struct DumpOptions {
+    pub layout: Layout,
+    /// Overwrites existing files instead of failing.
+    pub force: bool,
+    /// Removes the files of the previous dump that are listed in its manifest.
+    pub clean: bool,
+    /// Glob patterns of the full command names to dump, all if empty.
+    pub commands: Vec<String>,
+    /// Executors of the scripts to dump, all if empty.
+    pub executors: Vec<String>,
+    /// Prints the scripts with a header each instead of writing them.
+    pub stdout: bool,
+}
+
+impl DumpOptions {
+    /// Keeps the scripts that match any of the command patterns and executors.
+    fn filter<'a>(
+        &self,
+        scripts: &'a [ExtractedScript],
+    ) -> anyhow::Result<Vec<&'a ExtractedScript>> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in &self.commands {
+            builder.add(Glob::new(pattern)?);
+        }
+        let commands = builder.build()?;
+        // compares the programs, so python also matches python3 and env python3
+        let program = |executor: &str| parse_executor(executor).map(|e| e.program);
+        let executors: Vec<Option<String>> = self.executors.iter().map(|e| program(e)).collect();
+        Ok(scripts
+            .iter()
+            .filter(|s| self.commands.is_empty() || commands.is_match(&s.command))
+            .filter(|s| self.executors.is_empty() || executors.contains(&program(&s.executor)))
+            .collect())
+    }
+}
+
+/// The path of a command's script in the dir.
+pub fn script_path(dir: &Path, command: &str, extension: &str, layout: Layout) -> PathBuf {
+    match layout {
+        Layout::Flat => dir.join(format!("{}{extension}", command.replace(' ', "_"))),
+        Layout::Nested => {
+            let mut path = dir.to_path_buf();
+            let mut names = command.split(' ').peekable();
+            while let Some(name) = names.next() {
+                match names.peek() {
+                    Some(_) => path.push(name),
+                    None => path.push(format!("{name}{extension}")),
+                }
+            }
+            path
+        }
+    }
+}
+
+/// File name of the manifest that lists where the dumped scripts came from.
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    scripts: Vec<ManifestEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ManifestEntry {
+    /// Relative to the manifest.
+    file: PathBuf,
+    maskfile: String,
+    command: String,
+    /// The platform if the command has a script for windows and one for
+    /// everything else.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    variant: Option<String>,
+    executor: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    lines: Option<LineRange>,
+}
+
+/// The 1-based lines of the code block including the fences.
+#[derive(Serialize, Deserialize)]
+struct LineRange {
+    start: usize,
+    end: usize,
+}
+
+/// Writes the scripts to their paths, which must not exist yet unless they're
+/// overwritten, and the manifest into the dir.
+pub(crate) fn dump_scripts(
+    scripts: &[ExtractedScript],
+    maskfile_name: &str,
+    dir: &Path,
+    options: &DumpOptions,
+) -> anyhow::Result<()> {
+    let scripts = options.filter(scripts)?;
+    if options.stdout {
+        for script in scripts {
+            let content = with_provenance(script, maskfile_name);
+            println!("==> {maskfile_name}: {} <==", script.label());
+            print!("{content}");
+            if !content.ends_with('\n') {
+                println!();
+            }
+            println!();
+        }
+        return Ok(());
+    }
+    if options.clean {
+        clean(dir)?;
+    }
+    for script in &scripts {
+        if let Some(parent) = script.file_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = with_provenance(script, maskfile_name);
+        match write_file(&script.file_path, &content, options.force) {
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                return Err(anyhow!(
+                    "{} already exists, use --force to overwrite it",
+                    script.file_path.display()
+                ));
+            }
+            result => result?,
+        }
+    }
+    let manifest = Manifest {
+        scripts: scripts
+            .iter()
+            .map(|script| ManifestEntry {
+                file: script.file_path.strip_prefix(dir).unwrap_or(&script.file_path).to_path_buf(),
+                maskfile: maskfile_name.to_string(),
+                command: script.command.clone(),
+                variant: script.variant.map(str::to_string),
+                executor: script.executor.clone(),
+                lines: script.fence.map(|f| LineRange { start: f.start + 1, end: f.end + 1 }),
+            })
+            .collect(),
+    };
+    fs::write(dir.join(MANIFEST_FILE_NAME), serde_json::to_string_pretty(&manifest)? + "\n")?;
+    Ok(())
+}
+
+/// Packs the dumped files in the dir into an archive, which is compressed
+/// according to its extension, e.g. out.tar.gz.
+pub fn archive(dir: &Path, archive_path: &Path) -> anyhow::Result<()> {
+    // like for installing the linters, tar saves bundling archive formats
+    let output = Command::new("tar")
+        .arg("-caf")
+        .arg(archive_path)
+        .arg("-C")
+        .arg(dir)
+        .arg(".")
+        .output()
+        .context("tar is needed to create the archive but not installed")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "tar failed with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+/// Removes the files of the previous dump into the dir and the dirs that are
+/// empty afterwards. Other files are kept.
+fn clean(dir: &Path) -> anyhow::Result<()> {
+    let manifest_path = dir.join(MANIFEST_FILE_NAME);
+    let manifest: Manifest = match fs::read_to_string(&manifest_path) {
+        Ok(content) => serde_json::from_str(&content)?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+    for entry in manifest.scripts {
+        // a modified manifest must not remove anything outside of the dir
+        if !entry.file.components().all(|c| matches!(c, Component::Normal(_))) {
+            continue;
+        }
+        let path = dir.join(&entry.file);
+        debug!("removing {}", path.display());
+        match fs::remove_file(&path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+        for parent in path.ancestors().skip(1).take_while(|p| *p != dir) {
+            // fails if the dir isn't empty
+            if fs::remove_dir(parent).is_err() {
+                break;
+            }
+        }
+    }
+    fs::remove_file(manifest_path)?;
+    Ok(())
+}
+
+/// The comment that starts a line in the language of the file extension.
+fn line_comment(extension: &str) -> Option<&'static str> {
+    match extension {
+        "sh" | "zsh" | "fish" | "py" | "rb" | "nu" | "ps1" | "pl" | "R" => Some("#"),
+        "js" | "ts" | "php" => Some("//"),
+        "lua" | "sql" => Some("--"),
+        "bat" => Some("REM"),
+        _ => None,
+    }
+}
+
+/// Prepends comments to the script that tell where it came from, after the
+/// shebang or opening tag that has to stay in the first line. Scripts in
+/// unknown languages are left as they are.
+fn with_provenance(script: &ExtractedScript, maskfile_name: &str) -> String {
+    let extension = script.file_path.extension().unwrap_or_default().to_string_lossy();
+    let Some(comment) = line_comment(&extension) else {
+        return script.content.clone();
+    };
+    let mut header = format!("{comment} Dumped by masklint from {maskfile_name}\n");
+    header.push_str(&format!("{comment} Command: {}\n", script.command));
+    if let Some(fence) = script.fence {
+        header.push_str(&format!("{comment} Lines: {}-{}\n", fence.start + 1, fence.end + 1));
+    }
+    let content = &script.content;
+    match content.split_once('\n') {
+        Some((first, rest)) if first.starts_with("#!") || first.starts_with("<?php") => {
+            format!("{first}\n{header}{rest}")
+        }
+        _ => header + content,
+    }
+}
+
+/// Writes the content to the path, which must not exist yet unless it's
+/// overwritten.
+pub(crate) fn write_file(path: &Path, content: &str, overwrite: bool) -> io::Result<()> {
+    debug!("writing {}", path.display());
+    let mut options = File::options();
+    match overwrite {
+        true => options.write(true).create(true).truncate(true),
+        false => options.write(true).create_new(true),
+    };
+    options.open(path)?.write_all(content.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fences::Fence;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(Layout::Flat, "services api build", "out/services_api_build.sh")]
+    #[case(Layout::Nested, "services api build", "out/services/api/build.sh")]
+    #[case(Layout::Nested, "build", "out/build.sh")]
+    fn test_script_path(#[case] layout: Layout, #[case] command: &str, #[case] expected: &str) {
+        assert_eq!(script_path(Path::new("out"), command, ".sh", layout), Path::new(expected));
+    }
+
+    #[rstest]
+    #[case(
+        "deploy.py",
+        "print()\n",
+        "# Dumped by masklint from maskfile.md\n# Command: deploy\n# Lines: 3-5\nprint()\n"
+    )]
+    #[case("deploy.php", "<?php\necho 1;\n", "<?php\n// Dumped by masklint from maskfile.md\n// Command: deploy\n// Lines: 3-5\necho 1;\n")]
+    #[case("deploy", "echo\n", "echo\n")]
+    fn test_with_provenance(#[case] file: &str, #[case] content: &str, #[case] expected: &str) {
+        let script = ExtractedScript {
+            command: "deploy".to_string(),
+            variant: None,
+            executor: String::new(),
+            fence: Some(Fence { start: 2, end: 4 }),
+            stages: vec![],
+            file_name: file.to_string(),
+            file_path: PathBuf::from(file),
+            content: content.to_string(),
+            preamble: String::new(),
+        };
+        assert_eq!(with_provenance(&script, "maskfile.md"), expected);
+    }
+
+    #[test]
+    fn test_archive() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let dir = temp_dir.path().join("scripts");
+        fs::create_dir_all(dir.join("services")).unwrap();
+        fs::write(dir.join("services/api.sh"), "echo api\n").unwrap();
+        let archive_path = temp_dir.path().join("scripts.tar.gz");
+        archive(&dir, &archive_path).unwrap();
+
+        let output = Command::new("tar").arg("-tzf").arg(&archive_path).output().unwrap();
+        assert!(String::from_utf8_lossy(&output.stdout).lines().any(|l| l == "./services/api.sh"));
+    }
+}
@@ -0,0 +1,110 @@
+use anyhow::anyhow;
+use owo_colors::{Stream, Style};
+use std::{io, process::Command};
+
+use masklint::{color::paint, structure, MissingLinter};
+
+/// The linter that a rule code belongs to, told apart by the format of the code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Owner {
+    Maskfile,
+    Shellcheck,
+    Ruff,
+    Rubocop,
+}
+
+impl Owner {
+    fn of(code: &str) -> Option<Owner> {
+        let (prefix, number) = code.split_at(code.find(|c: char| c.is_ascii_digit())?);
+        let numbered = number.chars().all(|c| c.is_ascii_digit());
+        if code.starts_with("MF") && numbered {
+            return Some(Owner::Maskfile);
+        }
+        if prefix == "SC" && numbered {
+            return Some(Owner::Shellcheck);
+        }
+        // like E501, UP006 or PLR0913
+        if (1..=4).contains(&prefix.len()) && prefix.chars().all(|c| c.is_ascii_uppercase()) {
+            return numbered.then_some(Owner::Ruff);
+        }
+        None
+    }
+}
+
+/// Prints what the rule checks, taken from the linter that owns it, so the
+/// findings can be triaged without knowing which linter reported them.
+pub fn explain(code: &str) -> anyhow::Result<()> {
+    let (owner, explanation) = explanation(code)?;
+    let header = format!("{code} ({owner})");
+    println!("{}\n", paint(header, Stream::Stdout, Style::new().bold()));
+    println!("{}", explanation.trim_end());
+    Ok(())
+}
+
+fn explanation(code: &str) -> anyhow::Result<(&'static str, String)> {
+    // cops are named like Style/StringLiterals, including the ones of plugins
+    let owner = match code.split_once('/') {
+        Some((department, _)) if department.starts_with(char::is_uppercase) => Some(Owner::Rubocop),
+        _ => Owner::of(code),
+    };
+    match owner {
+        Some(Owner::Maskfile) => {
+            let (_, severity, description) = structure::RULES
+                .iter()
+                .find(|(rule, _, _)| *rule == code)
+                .ok_or_else(|| anyhow!("unknown maskfile rule {code}"))?;
+            Ok((structure::LINTER, format!("Severity: {severity}\n\n{description}.")))
+        }
+        // shellcheck only explains its rules in the wiki that its output links to
+        Some(Owner::Shellcheck) => {
+            Ok(("shellcheck", format!("See https://www.shellcheck.net/wiki/{code}")))
+        }
+        Some(Owner::Ruff) => Ok(("ruff", run("ruff", &["rule", code])?)),
+        Some(Owner::Rubocop) => Ok(("rubocop", run("rubocop", &["--show-cops", code])?)),
+        None => Err(anyhow!(
+            "unknown rule {code}, only the rules of shellcheck (e.g. SC2086), ruff (e.g. E501), \
+             rubocop (e.g. Style/StringLiterals) and the maskfile (e.g. MF003) can be explained"
+        )),
+    }
+}
+
+fn run(linter: &str, args: &[&str]) -> anyhow::Result<String> {
+    let output = Command::new(linter).args(args).output().map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => anyhow::Error::new(MissingLinter(linter.to_string())),
+        _ => anyhow!(e),
+    })?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    // rubocop lists nothing for unknown cops instead of failing
+    if !output.status.success() || stdout.trim().is_empty() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("{linter} can't explain {}: {}", args[args.len() - 1], stderr.trim()));
+    }
+    Ok(stdout.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("MF003", Some(Owner::Maskfile))]
+    #[case("SC2086", Some(Owner::Shellcheck))]
+    #[case("E501", Some(Owner::Ruff))]
+    #[case("PLR0913", Some(Owner::Ruff))]
+    #[case("RUF100", Some(Owner::Ruff))]
+    #[case("E501x", None)]
+    #[case("arg-type", None)]
+    fn test_owner(#[case] code: &str, #[case] expected: Option<Owner>) {
+        assert_eq!(Owner::of(code), expected);
+    }
+
+    #[test]
+    fn test_explanation() {
+        let (linter, text) = explanation("MF002").unwrap();
+        assert_eq!(linter, "maskfile");
+        assert_eq!(text, "Severity: warning\n\nThe command's code block is empty.");
+        assert!(explanation("MF999").is_err());
+        assert!(explanation("arg-type").is_err());
+    }
+}
@@ -0,0 +1,183 @@
+/// The position of a script's code block in the maskfile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fence {
+    /// 0-based line of the opening fence, the script starts in the next line.
+    pub start: usize,
+    /// 0-based line of the closing fence.
+    pub end: usize,
+}
+
+/// Finds the code block of every command's script with the full name of the
+/// command, since mask-parser doesn't keep the positions. Like mask-parser,
+/// the last code block of a command is its script, which on windows can also
+/// be a windows only one.
+pub fn script_fences(content: &str) -> Vec<(String, Fence)> {
+    fences(content, false)
+}
+
+/// Finds the code blocks of the scripts that mask only runs on windows, with
+/// the full name of their command.
+pub fn windows_fences(content: &str) -> Vec<(String, Fence)> {
+    fences(content, true)
+}
+
+fn fences(content: &str, windows: bool) -> Vec<(String, Fence)> {
+    let mut fences = vec![];
+    let mut in_command = false;
+    // the levels and full names of the current command and its parents
+    let mut commands: Vec<(usize, String)> = vec![];
+    let mut script: Option<Fence> = None;
+    // the fence character and whether the block is the current script
+    let mut open_fence = None;
+    let mut last_line = 0;
+    for (i, line) in content.lines().enumerate() {
+        last_line = i;
+        let line = line.trim();
+        if let Some((fence, is_script)) = open_fence {
+            if closes_fence(line, fence) {
+                open_fence = None;
+                if let (true, Some(script)) = (is_script, script.as_mut()) {
+                    script.end = i;
+                }
+            }
+            continue;
+        }
+        if let Some(fence) = opening_fence(line) {
+            let language = line.trim_start_matches(fence).trim();
+            let is_script =
+                in_command && (is_windows_only(language) == windows || cfg!(windows) && !windows);
+            if is_script {
+                script = Some(Fence { start: i, end: i });
+            }
+            open_fence = Some((fence, is_script));
+            continue;
+        }
+        let Some((level, text)) = heading(line) else {
+            continue;
+        };
+        let command = commands.last().map(|(_, name)| name.clone()).unwrap_or_default();
+        fences.extend(script.take().map(|script| (command, script)));
+        // mask stops at the second title
+        if level == 1 && in_command {
+            return fences;
+        }
+        in_command = level > 1;
+        commands.retain(|(parent, _)| *parent < level);
+        if in_command {
+            let parent = commands.last().map(|(_, name)| name.as_str());
+            commands.push((level, command_name(parent, text)));
+        }
+    }
+    // an unclosed code block runs until the end of the file
+    if let (Some((_, true)), Some(script)) = (open_fence, script.as_mut()) {
+        script.end = last_line;
+    }
+    let command = commands.last().map(|(_, name)| name.clone()).unwrap_or_default();
+    fences.extend(script.map(|script| (command, script)));
+    fences
+}
+
+/// The full name of a command from the text of its heading, without the
+/// arguments. Subcommands usually repeat the parent's name, but mask adds it
+/// otherwise.
+pub(crate) fn command_name(parent: Option<&str>, text: &str) -> String {
+    let name = text[..text.find(['(', '[']).unwrap_or(text.len())].trim();
+    match parent {
+        Some(parent) if !name.starts_with(parent) => format!("{parent} {name}"),
+        _ => name.to_string(),
+    }
+}
+
+/// The character of the code fence that the trimmed line opens.
+pub(crate) fn opening_fence(line: &str) -> Option<char> {
+    ['`', '~'].into_iter().find(|f| line.starts_with(&f.to_string().repeat(3)))
+}
+
+pub(crate) fn closes_fence(line: &str, fence: char) -> bool {
+    line.len() >= 3 && line.chars().all(|c| c == fence)
+}
+
+/// The level and the text of the trimmed line if it's a heading.
+pub(crate) fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let text = line[level..].strip_prefix(' ')?;
+    (level > 0).then_some((level, text.trim()))
+}
+
+/// mask ignores windows only scripts on other platforms.
+pub(crate) fn is_windows_only(language: &str) -> bool {
+    matches!(language, "powershell" | "batch" | "cmd")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script_fences() {
+        let maskfile = "# Tasks
+
+## build
+
+```bash
+echo $1
+```
+
+## docs
+
+> no script
+
+### docs serve
+
+~~~sh
+# not a heading
+python -m http.server
+~~~
+";
+        assert_eq!(
+            script_fences(maskfile),
+            vec![
+                ("build".to_string(), Fence { start: 4, end: 6 }),
+                ("docs serve".to_string(), Fence { start: 14, end: 17 })
+            ]
+        );
+        assert_eq!(script_fences("# Tasks\n\n```bash\necho\n```\n"), vec![]);
+        assert_eq!(
+            script_fences("## build (target)\n```sh\necho\n"),
+            vec![("build".to_string(), Fence { start: 1, end: 2 })]
+        );
+    }
+
+    #[test]
+    fn test_windows_fences() {
+        let maskfile = "# Tasks
+
+## build
+
+```bash
+make
+```
+
+```powershell
+msbuild
+```
+
+## clean
+
+```cmd
+del out
+```
+";
+        assert_eq!(
+            script_fences(maskfile),
+            vec![("build".to_string(), Fence { start: 4, end: 6 })]
+        );
+        assert_eq!(
+            windows_fences(maskfile),
+            vec![
+                ("build".to_string(), Fence { start: 8, end: 10 }),
+                ("clean".to_string(), Fence { start: 14, end: 16 })
+            ]
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use std::fmt::Display;
+
+/// Severity of a single finding, ordered from the least to the most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Style,
+    Info,
+    Warning,
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Style => write!(f, "style"),
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl Severity {
+    /// Maps the severity names and abbreviations used by the different linters.
+    fn from_keyword(keyword: &str) -> Option<Self> {
+        match keyword.to_lowercase().as_str() {
+            "error" | "fatal" | "parseerror" | "e" | "f" => Some(Severity::Error),
+            "warning" | "w" => Some(Severity::Warning),
+            "info" | "information" | "note" | "i" => Some(Severity::Info),
+            "style" | "convention" | "refactor" | "c" | "r" => Some(Severity::Style),
+            _ => None,
+        }
+    }
+}
+
+/// A single issue reported by a linter.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub rule: Option<String>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Finding {
+    /// Used for output that doesn't follow any known format.
+    pub fn unparsed(message: &str, severity: Severity) -> Self {
+        Finding { line: None, column: None, rule: None, severity, message: message.to_string() }
+    }
+}
+
+/// Parses findings from output lines like "line 3:5: W: msg" or "line 3: msg",
+/// which most handlers normalize the linter output to. The severity is taken
+/// from a keyword in front of the message if there is one.
+pub fn parse_line_findings(output: &str, default_severity: Severity) -> Vec<Finding> {
+    let mut findings = vec![];
+    for output_line in output.lines() {
+        let Some(rest) = output_line.strip_prefix("line ") else {
+            continue;
+        };
+        let Some((line, rest)) = split_number(rest) else {
+            continue;
+        };
+        let (column, rest) = match rest.strip_prefix(':').and_then(split_number) {
+            Some((column, rest)) => (Some(column), rest),
+            None => (None, rest),
+        };
+        let message = rest.trim_start_matches(':').trim();
+
+        let words: Vec<&str> = message.split_whitespace().collect();
+        let severity = words
+            .iter()
+            .take(3)
+            .find_map(|w| Severity::from_keyword(clean_word(w)))
+            .or_else(|| bracketed_severity(message))
+            .unwrap_or(default_severity);
+        let rule = words.iter().map(|w| clean_word(w)).find(|w| is_rule(w)).map(str::to_string);
+        findings.push(Finding {
+            line: Some(line),
+            column,
+            rule,
+            severity,
+            message: message.to_string(),
+        });
+    }
+    findings
+}
+
+/// Parses shellcheck's default output format where each finding is marked
+/// below the offending source line.
+///
+/// ```text
+/// In line 1:
+/// mkdir $unset
+///       ^----^ SC2154 (warning): unset is referenced but not assigned.
+/// ```
+pub fn parse_shellcheck_findings(output: &str) -> Vec<Finding> {
+    let mut findings = vec![];
+    let mut current_line = None;
+    for output_line in output.lines() {
+        if let Some(n) = output_line.strip_prefix("In line ").and_then(|l| l.strip_suffix(':')) {
+            current_line = n.parse::<usize>().ok();
+            continue;
+        }
+        let Some(marker_start) = output_line.find('^') else {
+            continue;
+        };
+        let Some(code_start) = output_line.find(" SC") else {
+            continue;
+        };
+        let details = &output_line[code_start + 1..];
+        let Some((rule, rest)) = details.split_once(' ') else {
+            continue;
+        };
+        let Some((severity, message)) = rest.strip_prefix('(').and_then(|r| r.split_once("): "))
+        else {
+            continue;
+        };
+        findings.push(Finding {
+            line: current_line,
+            column: Some(marker_start + 1),
+            rule: Some(rule.to_string()),
+            severity: Severity::from_keyword(severity).unwrap_or(Severity::Warning),
+            message: message.to_string(),
+        });
+    }
+    findings
+}
+
+fn split_number(s: &str) -> Option<(usize, &str)> {
+    let digits = s.chars().take_while(|c| c.is_ascii_digit()).count();
+    let number = s[..digits].parse().ok()?;
+    Some((number, &s[digits..]))
+}
+
+fn clean_word(word: &str) -> &str {
+    word.trim_matches(|c: char| matches!(c, '(' | ')' | '[' | ']' | ':' | ',' | '-'))
+}
+
+/// Finds severities like "[Error/no-undef]" as printed by eslint.
+fn bracketed_severity(message: &str) -> Option<Severity> {
+    let start = message.rfind('[')?;
+    let (keyword, _) = message[start + 1..].split_once('/')?;
+    Severity::from_keyword(keyword)
+}
+
+/// Checks if the word looks like a rule code, e.g. E703, SC2086, LT01 or
+/// Style/StringLiterals.
+fn is_rule(word: &str) -> bool {
+    let letters = word.chars().take_while(|c| c.is_ascii_uppercase()).count();
+    let is_code =
+        letters > 0 && letters < word.len() && word[letters..].chars().all(|c| c.is_ascii_digit());
+    let is_cop = word.split_once('/').is_some_and(|(department, cop)| {
+        department.starts_with(|c: char| c.is_ascii_uppercase())
+            && cop.starts_with(|c: char| c.is_ascii_uppercase())
+    });
+    is_code || is_cop
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(
+        "line 2:34: E703 [*] Statement ends with a semicolon",
+        Some(34),
+        Some("E703"),
+        Severity::Warning
+    )]
+    #[case(
+        "line 1:12: C: [Correctable] Style/StringLiterals: Prefer single-quoted strings.",
+        Some(12),
+        Some("Style/StringLiterals"),
+        Severity::Style
+    )]
+    #[case("line 3: error: Missing return statement", None, None, Severity::Error)]
+    #[case("line 4:1: Unexpected token [Error/no-undef]", Some(1), None, Severity::Error)]
+    #[case("line 7:3: (W211) unused variable 'x'", Some(3), Some("W211"), Severity::Warning)]
+    fn test_parse_line_findings(
+        #[case] output: &str,
+        #[case] column: Option<usize>,
+        #[case] rule: Option<&str>,
+        #[case] severity: Severity,
+    ) {
+        let findings = parse_line_findings(output, Severity::Warning);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].column, column);
+        assert_eq!(findings[0].rule.as_deref(), rule);
+        assert_eq!(findings[0].severity, severity);
+    }
+
+    #[test]
+    fn test_parse_shellcheck_findings() {
+        let output = "In line 1:
+mkdir $unset
+      ^----^ SC2154 (warning): unset is referenced but not assigned.
+      ^----^ SC2086 (info): Double quote to prevent globbing and word splitting.
+
+Did you mean:
+mkdir \"$unset\"";
+        let findings = parse_shellcheck_findings(output);
+        assert_eq!(
+            findings,
+            vec![
+                Finding {
+                    line: Some(1),
+                    column: Some(7),
+                    rule: Some("SC2154".to_string()),
+                    severity: Severity::Warning,
+                    message: "unset is referenced but not assigned.".to_string(),
+                },
+                Finding {
+                    line: Some(1),
+                    column: Some(7),
+                    rule: Some("SC2086".to_string()),
+                    severity: Severity::Info,
+                    message: "Double quote to prevent globbing and word splitting.".to_string(),
+                },
+            ]
+        );
+    }
+}
@@ -1,12 +1,27 @@
+use crate::{
+    config::{Config, JavaScriptLinter},
+    findings::{parse_line_findings, parse_shellcheck_findings, Finding, Severity},
+};
+use clap::ValueEnum;
+use log::{debug, trace};
 use mask_parser::maskfile::Script;
+use serde::{Deserialize, Serialize};
 use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    env,
+    ffi::{OsStr, OsString},
     fmt::{Debug, Display},
-    io,
-    path::Path,
-    process::Command,
+    fs, io,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    process::{Child, Command, ExitStatus, Output, Stdio},
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum LintResultType {
     Warning,
     Findings,
@@ -16,26 +31,685 @@ pub enum LintResultType {
 pub struct LintResult {
     pub message: String,
     pub result_type: LintResultType,
+    /// The individual findings parsed from the message.
+    pub findings: Vec<Finding>,
 }
 
 impl LintResult {
     pub fn warning(message: String) -> Self {
-        LintResult { message, result_type: LintResultType::Warning }
+        LintResult { message, result_type: LintResultType::Warning, findings: vec![] }
     }
 
     pub fn findings(message: String) -> Self {
-        LintResult { message, result_type: LintResultType::Findings }
+        LintResult { message, result_type: LintResultType::Findings, findings: vec![] }
+    }
+
+    /// A single error finding, e.g. if the linter couldn't be run properly.
+    pub fn error(message: String) -> Self {
+        let findings = vec![Finding::unparsed(&message, Severity::Error)];
+        LintResult { message, result_type: LintResultType::Findings, findings }
+    }
+
+    /// Parses the individual findings out of the message. Output that can't be
+    /// parsed still counts as a single error, so it's never silently dropped.
+    pub fn parse(mut self, handler: &dyn LanguageHandler) -> Self {
+        if !matches!(self.result_type, LintResultType::Findings) || self.message.is_empty() {
+            return self;
+        }
+        self.findings = handler.parse_findings(&self.message);
+        if self.findings.is_empty() {
+            self.findings = vec![Finding::unparsed(&self.message, Severity::Error)];
+        }
+        self
+    }
+
+    /// Combines the results of all stages of a pipeline. The messages are
+    /// labeled with the linter name if more than one linter reported something.
+    pub fn merge(results: Vec<(String, LintResult)>) -> Self {
+        let results: Vec<(String, LintResult)> =
+            results.into_iter().filter(|(_, r)| !r.message.is_empty()).collect();
+        let result_type =
+            if results.iter().any(|(_, r)| matches!(r.result_type, LintResultType::Findings)) {
+                LintResultType::Findings
+            } else {
+                LintResultType::Warning
+            };
+        let message = match results.as_slice() {
+            [(_, result)] => result.message.clone(),
+            _ => results
+                .iter()
+                .map(|(linter, r)| format!("[{linter}]\n{}", r.message))
+                .collect::<Vec<String>>()
+                .join("\n\n"),
+        };
+        let findings = results.into_iter().flat_map(|(_, r)| r.findings).collect();
+        LintResult { message, result_type, findings }
+    }
+}
+
+/// A single linter in the pipeline of an executor.
+pub struct Stage {
+    pub handler: Box<dyn LanguageHandler>,
+    pub enabled: bool,
+}
+
+/// Returns the linters that are run one after another for the script. Only the
+/// main linter of each language is enabled by default, the others need to be
+/// enabled in the config. The first stage also defines how the script file is
+/// written.
+pub fn pipeline(script: &Script, variables: &[String], config: &Config) -> Vec<Stage> {
+    let variables: Vec<String> =
+        variables.iter().chain(&config.shell.runtime_variables).cloned().collect();
+    let variables = variables.as_slice();
+    // unknown executors might just be custom aliases, so the shebang is
+    // checked before giving up on the script
+    let stages = parse_executor(&script.executor)
+        .and_then(|executor| known_stages(&executor, &script.source, variables, config))
+        .or_else(|| {
+            shebang_executor(&script.source)
+                .and_then(|e| known_stages(&e, &script.source, variables, config))
+        })
+        .unwrap_or_else(|| vec![(Box::new(Catchall), true)]);
+    stages
+        .into_iter()
+        .map(|(handler, default)| {
+            let enabled = config.linter_enabled(&handler.to_string(), default);
+            Stage { handler, enabled }
+        })
+        .collect()
+}
+
+fn known_stages(
+    executor: &Executor,
+    source: &str,
+    variables: &[String],
+    config: &Config,
+) -> Option<Vec<(Box<dyn LanguageHandler>, bool)>> {
+    let stages: Vec<(Box<dyn LanguageHandler>, bool)> = match executor.program.as_str() {
+        "sh" | "bash" | "dash" | "ksh" => vec![
+            (
+                Box::new(Shellcheck {
+                    shell: executor.program.clone(),
+                    variables: variables.to_vec(),
+                    options: executor.shell_options(),
+                }),
+                true,
+            ),
+            (Box::new(Shfmt), false),
+        ],
+        "py" | "python" => vec![(Box::new(Ruff), true), (Box::new(Mypy), false)],
+        "rb" | "ruby" => vec![(Box::new(Rubocop), true), (Box::new(RubySyntax), false)],
+        "nu" | "nushell" => vec![(Box::new(Nushell), true)],
+        "powershell" | "pwsh" => vec![(Box::new(PowerShell), true)],
+        "fish" => vec![(Box::new(Fish), true)],
+        "zsh" => vec![(
+            Box::new(Zsh { shellcheck: config.zsh.shellcheck, variables: variables.to_vec() }),
+            true,
+        )],
+        "js" | "javascript" | "node" => {
+            vec![(Box::new(JavaScript { linter: config.javascript.linter }), true)]
+        }
+        "ts" | "typescript" | "deno" => {
+            vec![(Box::new(Deno { check: config.typescript.check }), true)]
+        }
+        "lua" => vec![(Box::new(Lua), true)],
+        "perl" => vec![(Box::new(Perl), true)],
+        "php" => {
+            vec![(Box::new(Php { opening_tag: !source.trim_start().starts_with("<?php") }), true)]
+        }
+        "r" | "R" | "rscript" | "Rscript" => vec![(Box::new(Rlang), true)],
+        "cmd" | "batch" | "bat" => vec![(Box::new(Batch), true)],
+        "sql" => vec![(Box::new(Sql { dialect: config.sql.dialect.clone() }), true)],
+        _ => return None,
+    };
+    Some(stages)
+}
+
+/// The interpreter of a script and the arguments it's run with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Executor {
+    /// The name without the path and version, e.g. `python` for `/usr/bin/python3`.
+    pub(crate) program: String,
+    pub(crate) args: Vec<String>,
+}
+
+impl Executor {
+    /// The options that are set with `set` in shells, like -e or -o pipefail.
+    /// Options that only make sense when starting the shell, e.g. -c, are left out.
+    fn shell_options(&self) -> Vec<String> {
+        let mut options = vec![];
+        let mut args = self.args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-o" | "+o" => {
+                    let Some(name) = args.next() else {
+                        break;
+                    };
+                    options.extend([arg.clone(), name.clone()]);
+                }
+                _ if arg.len() > 1
+                    && arg.starts_with(['-', '+'])
+                    && arg[1..]
+                        .chars()
+                        .all(|c| c.is_ascii_alphabetic() && !"cilrs".contains(c)) =>
+                {
+                    options.push(arg.clone());
+                }
+                _ => {}
+            }
+        }
+        options
+    }
+}
+
+/// Splits an executor like `bash -e`, `python3 -u` or `/usr/bin/env ruby3.2`
+/// into the program and its arguments.
+pub(crate) fn parse_executor(executor: &str) -> Option<Executor> {
+    let mut words = executor.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        // skips flags like -S
+        program = words.find(|a| !a.starts_with('-'))?;
+    }
+    let program = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    Some(Executor { program: program.to_string(), args: words.map(str::to_string).collect() })
+}
+
+/// Extracts the interpreter from a shebang like `#!/usr/bin/env python3` or
+/// `#!/bin/bash -e`. Version suffixes are dropped, so the first one is `python`.
+fn shebang_executor(source: &str) -> Option<Executor> {
+    let shebang = source.trim_start().lines().next()?.strip_prefix("#!")?;
+    parse_executor(shebang)
+}
+
+/// Runs linter commands with logging, so it's visible what exactly is run.
+trait CommandExt {
+    fn logged_output(&mut self, settings: &RunSettings) -> io::Result<Output>;
+    /// Passes the input over stdin instead of inheriting it.
+    fn logged_output_with_stdin(
+        &mut self,
+        input: &str,
+        settings: &RunSettings,
+    ) -> io::Result<Output>;
+}
+
+/// The `--version` output per command, the installed linters don't change
+/// while masklint runs.
+static VERSIONS: Mutex<BTreeMap<String, Option<String>>> = Mutex::new(BTreeMap::new());
+
+/// What a linter is run with besides the script, set per linter from the
+/// options and the config.
+#[derive(Debug, Clone, Default)]
+pub struct RunSettings {
+    /// The maximum time the linter may take.
+    pub timeout: Option<Duration>,
+    /// The container the linter is run in instead of on the host, if any.
+    pub container: Option<Container>,
+    /// The configured command that the linter is run with, if any.
+    pub wrapper: Option<Wrapper>,
+    /// Where the linter takes its config from, by default it looks for it
+    /// next to the scripts in the temp dir.
+    pub config_source: Option<ConfigSource>,
+    /// Whether the linter may be run through its server.
+    pub daemons: bool,
+    /// The linters whose server failed, shared by all runs.
+    pub failed_daemons: FailedDaemons,
+    /// The configured variables and working dir of the linter, if any.
+    pub environment: Option<Environment>,
+}
+
+/// Linters whose server failed, e.g. because the installed version doesn't
+/// have one yet. They are run without it from then on.
+pub type FailedDaemons = Arc<Mutex<BTreeSet<&'static str>>>;
+
+impl RunSettings {
+    /// Runs the executable with `--version`, with the wrapper and the
+    /// container, once per process. Returns stdout and stderr combined, or
+    /// nothing if it couldn't be run, e.g. because it's not installed.
+    pub fn version_output(&self, executable: &str) -> Option<String> {
+        let wrapper = self.wrapper.as_ref().map(|w| &w.command);
+        let image = self.container.as_ref().map(|c| &c.image);
+        let key = format!("{executable} {wrapper:?} {image:?}");
+        let mut versions = VERSIONS.lock().unwrap_or_else(|e| e.into_inner());
+        versions
+            .entry(key)
+            .or_insert_with(|| {
+                let output = Command::new(executable).arg("--version").logged_output(self).ok()?;
+                let mut version = String::from_utf8_lossy(&output.stdout).to_string();
+                version.push_str(&String::from_utf8_lossy(&output.stderr));
+                Some(version)
+            })
+            .clone()
+    }
+
+    /// Describes what the linter is run with besides the script, e.g. the
+    /// wrapper, so cached results of other settings aren't used.
+    pub fn describe(&self, handler: &dyn LanguageHandler) -> String {
+        let wrapper = self.wrapper.as_ref().map(|w| &w.command);
+        let image = self.container.as_ref().map(|c| &c.image);
+        let config_files = handler.config_files();
+        let config = match &self.config_source {
+            _ if config_files.is_empty() => "none".to_string(),
+            Some(ConfigSource::Project(dir)) => match config_file(dir, config_files) {
+                Some(path) => {
+                    let content = fs::read_to_string(&path).unwrap_or_default();
+                    format!("{}\n{content}", path.display())
+                }
+                None => "defaults".to_string(),
+            },
+            Some(ConfigSource::Isolated) => "isolated".to_string(),
+            None => "next to the script".to_string(),
+        };
+        let environment = &self.environment;
+        format!("wrapper: {wrapper:?}, image: {image:?}, config: {config}, env: {environment:?}")
+    }
+
+    /// Servers are started for the dir they're run in and keep running after
+    /// masklint exits, so they're only used in the project dir and never in
+    /// the throwaway containers.
+    fn use_daemon(&self, linter: &str) -> bool {
+        self.daemons
+            && self.container.is_none()
+            && matches!(self.config_source, Some(ConfigSource::Project(_)))
+            && !self.failed_daemons.lock().unwrap_or_else(|e| e.into_inner()).contains(linter)
+    }
+
+    /// Runs the linter through its server if possible and falls back to a run
+    /// without it if the server fails.
+    fn daemon_output(
+        &self,
+        linter: &'static str,
+        max_code: i32,
+        run: impl Fn(bool) -> io::Result<Output>,
+    ) -> io::Result<Output> {
+        if self.use_daemon(linter) {
+            let output = run(true)?;
+            match check_exit(linter, &output, max_code) {
+                Ok(()) => return Ok(output),
+                Err(e) => {
+                    debug!("running {linter} without its server from now on: {e}");
+                    self.failed_daemons.lock().unwrap_or_else(|e| e.into_inner()).insert(linter);
+                }
+            }
+        }
+        let output = run(false)?;
+        check_exit(linter, &output, max_code)?;
+        Ok(output)
+    }
+}
+
+/// Finds the first version number in the output of `--version`, e.g. `0.9.0`
+/// in `version: 0.9.0`. Trailing zeros are dropped, so `0.9` and `0.9.0`
+/// compare as equal.
+pub fn parse_version(output: &str) -> Option<Vec<u64>> {
+    let word = output
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .map(|w| w.trim_start_matches('v'))
+        .find(|w| w.starts_with(|c: char| c.is_ascii_digit()))?;
+    // pre-releases like 1.2.0-rc1 count as the release
+    let end = word.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(word.len());
+    let mut version: Vec<u64> = word[..end].split('.').map_while(|p| p.parse().ok()).collect();
+    while version.last() == Some(&0) {
+        version.pop();
+    }
+    Some(version)
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Runner {
+    /// Run the linters installed on the host
+    #[default]
+    Native,
+    /// Run the linters that have an image in docker containers
+    Docker,
+}
+
+/// A docker image that provides the executables of a linter on its PATH.
+#[derive(Debug, Clone)]
+pub struct Container {
+    pub image: String,
+    /// Mounted at the same path, so the paths of the scripts stay valid.
+    pub mount: PathBuf,
+}
+
+impl Container {
+    /// The image that is used for the linter unless another one is configured.
+    pub fn default_image(linter: &str) -> Option<&'static str> {
+        match linter {
+            "shellcheck" => Some("koalaman/shellcheck-alpine:stable"),
+            "ruff" => Some("ghcr.io/astral-sh/ruff:alpine"),
+            "rubocop" => Some("pipelinecomponents/rubocop:latest"),
+            "nushell" => Some("ghcr.io/nushell/nushell:latest"),
+            _ => None,
+        }
+    }
+
+    /// Wraps the command in a `docker run` of the image.
+    fn command(&self, command: &Command) -> Command {
+        let mut mount = self.mount.clone().into_os_string();
+        mount.push(":");
+        mount.push(&self.mount);
+        let mut docker = Command::new("docker");
+        docker
+            .args(["run", "--rm", "--interactive", "--network=none", "--volume"])
+            .arg(mount)
+            .arg("--workdir")
+            .arg(&self.mount);
+        for (key, value) in command.get_envs() {
+            if let Some(value) = value {
+                let mut env = key.to_os_string();
+                env.push("=");
+                env.push(value);
+                docker.arg("--env").arg(env);
+            }
+        }
+        docker.arg("--entrypoint").arg(command.get_program()).arg(&self.image);
+        docker.args(command.get_args());
+        docker
+    }
+}
+
+/// Replaces the main executable of a linter with a configured command, e.g.
+/// `bundle exec rubocop` to use the version from the Gemfile.
+#[derive(Debug, Clone)]
+pub struct Wrapper {
+    pub executable: &'static str,
+    pub command: Vec<String>,
+}
+
+impl Wrapper {
+    /// Returns the wrapped command if it runs the executable.
+    fn command(&self, command: &Command) -> Option<Command> {
+        let (program, args) = self.command.split_first()?;
+        if command.get_program() != self.executable {
+            return None;
+        }
+        let mut wrapped = Command::new(program);
+        wrapped.args(args).args(command.get_args());
+        if let Some(dir) = command.get_current_dir() {
+            wrapped.current_dir(dir);
+        }
+        for (key, value) in command.get_envs() {
+            match value {
+                Some(value) => wrapped.env(key, value),
+                None => wrapped.env_remove(key),
+            };
+        }
+        Some(wrapped)
+    }
+}
+
+/// The configured environment of a linter's processes.
+#[derive(Debug, Clone, Default)]
+pub struct Environment {
+    /// Only these variables of masklint's environment are passed on, if set.
+    pub allow: Option<Vec<String>>,
+    pub vars: BTreeMap<String, String>,
+    pub working_dir: Option<PathBuf>,
+}
+
+impl Environment {
+    /// Containers don't inherit masklint's environment and have a fixed
+    /// working dir, so only the variables are passed to them.
+    fn apply(&self, command: &mut Command, in_container: bool) {
+        if let (Some(allow), false) = (&self.allow, in_container) {
+            // the variables that the handler set itself are kept
+            let explicit: Vec<(OsString, Option<OsString>)> = command
+                .get_envs()
+                .map(|(key, value)| (key.to_os_string(), value.map(OsStr::to_os_string)))
+                .collect();
+            command.env_clear();
+            // the linters couldn't be found without the PATH
+            for key in allow.iter().map(String::as_str).chain(["PATH"]) {
+                if let Some(value) = env::var_os(key) {
+                    command.env(key, value);
+                }
+            }
+            for (key, value) in explicit.into_iter().filter_map(|(k, v)| Some((k, v?))) {
+                command.env(key, value);
+            }
+        }
+        if let (Some(dir), false) = (&self.working_dir, in_container) {
+            command.current_dir(dir);
+        }
+        command.envs(&self.vars);
+    }
+}
+
+/// Where linters that support it take their config from, since the scripts
+/// are linted in a temp dir outside of the project.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    /// The closest config in the dir or its parents, the linter is run in it.
+    Project(PathBuf),
+    /// Only the linter's defaults, ignoring any config.
+    Isolated,
+}
+
+impl ConfigSource {
+    /// Runs the command in the project dir and points it to the first of the
+    /// config files that is found, or adds the flag that ignores all configs.
+    fn apply(&self, command: &mut Command, config_files: &[&str], isolated_flag: &str) {
+        match self {
+            ConfigSource::Project(dir) => {
+                command.current_dir(dir);
+                if let Some(config) = config_file(dir, config_files) {
+                    command.arg("--config").arg(config);
+                }
+            }
+            ConfigSource::Isolated => {
+                command.arg(isolated_flag);
+            }
+        }
+    }
+}
+
+/// The first of the config files that is found in the dir or its parents.
+fn config_file(dir: &Path, config_files: &[&str]) -> Option<PathBuf> {
+    dir.ancestors()
+        .flat_map(|d| config_files.iter().map(move |f| d.join(f)))
+        .find(|p| is_config_file(p))
+}
+
+/// Like ruff, a pyproject.toml only counts if it has a section for ruff.
+fn is_config_file(path: &Path) -> bool {
+    if path.file_name().is_some_and(|n| n == "pyproject.toml") {
+        return fs::read_to_string(path).is_ok_and(|c| c.contains("[tool.ruff"));
+    }
+    path.is_file()
+}
+
+impl CommandExt for Command {
+    fn logged_output(&mut self, settings: &RunSettings) -> io::Result<Output> {
+        run_logged(self, None, settings)
+    }
+
+    fn logged_output_with_stdin(
+        &mut self,
+        input: &str,
+        settings: &RunSettings,
+    ) -> io::Result<Output> {
+        run_logged(self, Some(input), settings)
+    }
+}
+
+fn run_logged(
+    command: &mut Command,
+    input: Option<&str>,
+    settings: &RunSettings,
+) -> io::Result<Output> {
+    let mut wrapped = settings.wrapper.as_ref().and_then(|w| w.command(command));
+    let command = wrapped.as_mut().unwrap_or(command);
+    let in_container = settings.container.is_some();
+    if let Some(environment) = &settings.environment {
+        environment.apply(command, in_container);
+    }
+    let mut containerized = settings.container.as_ref().map(|c| c.command(command));
+    let command = containerized.as_mut().unwrap_or(command);
+    command.stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() });
+    match input {
+        Some(_) => debug!("running {command:?} with the script on stdin"),
+        None => debug!("running {command:?}"),
+    }
+    let start = Instant::now();
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .inspect_err(|e| debug!("failed to run {command:?}: {e}"))
+        .map_err(|e| match e.kind() {
+            // otherwise it would be reported as the linter not being installed
+            io::ErrorKind::NotFound if in_container => {
+                io::Error::other("docker is needed for the docker runner but not installed")
+            }
+            _ => e,
+        })?;
+    // the pipes are handled by other threads so a linter that already prints
+    // while reading can't block on a full pipe. They are not joined if the
+    // linter times out since processes started by it might keep them open.
+    let writer = child.stdin.take().map(|mut stdin| {
+        let input = input.unwrap_or_default().to_string();
+        thread::spawn(move || stdin.write_all(input.as_bytes()))
+    });
+    let stdout = read_pipe(child.stdout.take());
+    let stderr = read_pipe(child.stderr.take());
+    let status = wait(&mut child, settings.timeout)
+        .inspect_err(|e| debug!("failed to run {command:?}: {e}"))?;
+    if let Some(Ok(Err(e))) = writer.map(|w| w.join()) {
+        // linters might exit before reading everything, e.g. on errors
+        if e.kind() != io::ErrorKind::BrokenPipe {
+            return Err(e);
+        }
+    }
+    let output = Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    };
+    debug!(
+        "{} exited with {} after {:.2?}",
+        command.get_program().to_string_lossy(),
+        output.status,
+        start.elapsed()
+    );
+    trace!("stdout: {}", String::from_utf8_lossy(&output.stdout));
+    trace!("stderr: {}", String::from_utf8_lossy(&output.stderr));
+    Ok(output)
+}
+
+/// Fails if the linter crashed or exited with a code that it doesn't use for
+/// reporting findings, e.g. because of an invalid config. Otherwise it could
+/// look like there are no findings since only stdout is parsed.
+fn check_exit(linter: &str, output: &Output, max_code: i32) -> io::Result<()> {
+    if output.status.code().is_some_and(|code| code <= max_code) {
+        return Ok(());
+    }
+    Err(exit_error(linter, output))
+}
+
+/// Like [`check_exit`] for linters whose codes for findings and for their own
+/// errors are mixed, e.g. 255 for syntax errors but 1 or 2 for other errors.
+fn check_exit_codes(linter: &str, output: &Output, codes: &[i32]) -> io::Result<()> {
+    if output.status.code().is_some_and(|code| codes.contains(&code)) {
+        return Ok(());
+    }
+    Err(exit_error(linter, output))
+}
+
+fn exit_error(linter: &str, output: &Output) -> io::Error {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    io::Error::other(format!("{linter} failed with {}: {}", output.status, stderr.trim()))
+}
+
+fn read_pipe(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = vec![];
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+/// Waits for the linter to exit and kills it if it takes too long.
+fn wait(child: &mut Child, timeout: Option<Duration>) -> io::Result<ExitStatus> {
+    let Some(timeout) = timeout else {
+        return child.wait();
+    };
+    let start = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        if start.elapsed() >= timeout {
+            // the linter might have exited in the meantime
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("timed out after {timeout:?}"),
+            ));
+        }
+        thread::sleep(Duration::from_millis(10));
     }
 }
 
-pub trait LanguageHandler: Display {
+pub trait LanguageHandler: Display + Debug {
+    /// The programs that are run, their versions are part of the cache key.
+    fn executables(&self) -> Vec<&'static str> {
+        vec![]
+    }
     fn file_extension(&self) -> &'static str {
         ""
     }
+    /// The config files that are taken from the project, in the order they
+    /// are looked for in each dir.
+    fn config_files(&self) -> &'static [&'static str] {
+        &[]
+    }
     fn content(&self, script: &Script) -> Result<String, io::Error> {
         Ok(script.source.clone())
     }
-    fn execute(&self, path: &Path) -> Result<LintResult, io::Error>;
+    /// Lines that are only added in front of the script when it's linted,
+    /// e.g. to declare the variables that mask sets. The findings are shifted
+    /// back by them, and they aren't part of the dumped scripts.
+    fn preamble(&self) -> String {
+        String::new()
+    }
+    fn execute(&self, path: &Path, settings: &RunSettings) -> Result<LintResult, io::Error>;
+    /// Whether the linter can read the script from stdin, so it doesn't need
+    /// to be written to a file.
+    fn supports_stdin(&self) -> bool {
+        false
+    }
+    /// Lints the script passed over stdin. The file name is passed to the
+    /// linter as a hint, e.g. to find its config.
+    fn execute_stdin(
+        &self,
+        _content: &str,
+        _file_name: &str,
+        _settings: &RunSettings,
+    ) -> Result<LintResult, io::Error> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, format!("{self} can't lint from stdin")))
+    }
+    /// Linters that can lint multiple scripts in a single run return a key
+    /// here. Scripts whose handlers have the same key are linted together.
+    fn batch_key(&self) -> Option<String> {
+        None
+    }
+    /// Lints all the scripts at once and returns the results in the same order.
+    fn execute_batch(
+        &self,
+        paths: &[&Path],
+        settings: &RunSettings,
+    ) -> Result<Vec<LintResult>, io::Error> {
+        paths.iter().map(|p| self.execute(p, settings)).collect()
+    }
+    /// Splits the linter output into single findings. By default this expects
+    /// the "line 3:5: message" format that most handlers normalize to.
+    fn parse_findings(&self, output: &str) -> Vec<Finding> {
+        parse_line_findings(output, Severity::Error)
+    }
 }
 
 #[derive(Debug)]
@@ -46,13 +720,21 @@ impl Display for Catchall {
     }
 }
 impl LanguageHandler for Catchall {
-    fn execute(&self, _: &Path) -> Result<LintResult, io::Error> {
+    fn execute(&self, _: &Path, _: &RunSettings) -> Result<LintResult, io::Error> {
         Ok(LintResult::warning("no linter found for target".to_string()))
     }
 }
 
 #[derive(Debug)]
-pub struct Shellcheck;
+pub struct Shellcheck {
+    /// The shell dialect passed to shellcheck, one of sh, bash, dash or ksh.
+    pub shell: String,
+    /// Variables that are set by mask, like the command's arguments and options.
+    pub variables: Vec<String>,
+    /// Options the shell is started with, like -e, which are set in the
+    /// script, so shellcheck knows about them.
+    pub options: Vec<String>,
+}
 impl Display for Shellcheck {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "shellcheck")
@@ -60,23 +742,159 @@ impl Display for Shellcheck {
 }
 
 impl LanguageHandler for Shellcheck {
+    fn executables(&self) -> Vec<&'static str> {
+        vec!["shellcheck"]
+    }
     fn file_extension(&self) -> &'static str {
         ".sh"
     }
-    fn execute(&self, path: &Path) -> Result<LintResult, io::Error> {
-        let output = Command::new("shellcheck").arg(path).output()?;
-        let findings = String::from_utf8_lossy(&output.stdout)
-            .trim()
-            .replace(&format!("{} ", path.to_string_lossy()), "");
-        Ok(LintResult::findings(findings))
+    fn execute(&self, path: &Path, settings: &RunSettings) -> Result<LintResult, io::Error> {
+        let output = self.command().arg(path).logged_output(settings)?;
+        check_exit("shellcheck", &output, 1)?;
+        Ok(self.result(&String::from_utf8_lossy(&output.stdout), &path.to_string_lossy()))
     }
-    fn content(&self, script: &Script) -> Result<String, io::Error> {
-        let mut res = format!("#!/bin/usr/env {}\n", script.executor);
-        res.push_str(&script.source);
-        Ok(res)
+    fn supports_stdin(&self) -> bool {
+        true
+    }
+    fn execute_stdin(
+        &self,
+        content: &str,
+        _: &str,
+        settings: &RunSettings,
+    ) -> Result<LintResult, io::Error> {
+        let output = self.command().arg("-").logged_output_with_stdin(content, settings)?;
+        check_exit("shellcheck", &output, 1)?;
+        Ok(self.result(&String::from_utf8_lossy(&output.stdout), "-"))
+    }
+    fn batch_key(&self) -> Option<String> {
+        // the preamble decides by how much the line numbers are shifted
+        Some(format!("shellcheck --shell={} {}", self.shell, self.preamble_lines()))
+    }
+    fn execute_batch(
+        &self,
+        paths: &[&Path],
+        settings: &RunSettings,
+    ) -> Result<Vec<LintResult>, io::Error> {
+        let output = self.command().args(paths).logged_output(settings)?;
+        check_exit("shellcheck", &output, 1)?;
+        let output = String::from_utf8_lossy(&output.stdout);
+        // the links to the wiki are listed once at the end for all scripts
+        let (output, links) = output.split_once("For more information:").unwrap_or((&output, ""));
+        let outputs = split_output(output, paths, |path| format!("In {path} line "));
+        Ok(paths
+            .iter()
+            .zip(outputs)
+            .map(|(path, mut output)| {
+                let links: Vec<&str> = links
+                    .lines()
+                    .filter(|l| {
+                        let url = l.split_whitespace().next().unwrap_or_default();
+                        url.rsplit_once("/SC")
+                            .is_some_and(|(_, code)| output.contains(&format!(" SC{code} ")))
+                    })
+                    .collect();
+                if !links.is_empty() {
+                    output =
+                        format!("{}\n\nFor more information:\n{}", output.trim(), links.join("\n"));
+                }
+                self.result(&output, &path.to_string_lossy())
+            })
+            .collect())
+    }
+    fn parse_findings(&self, output: &str) -> Vec<Finding> {
+        parse_shellcheck_findings(output)
+    }
+    fn preamble(&self) -> String {
+        let mut preamble = shell_preamble(&self.variables).unwrap_or_default();
+        if !self.options.is_empty() {
+            preamble.push_str(&format!("set {}\n", self.options.join(" ")));
+        }
+        preamble
+    }
+}
+
+impl Shellcheck {
+    fn command(&self) -> Command {
+        let mut command = Command::new("shellcheck");
+        command.arg(format!("--shell={}", self.shell));
+        command
+    }
+
+    /// Strips the script path, so only "In line 1:" is left.
+    fn result(&self, output: &str, script: &str) -> LintResult {
+        let findings = output.trim().replace(&format!("In {script} line "), "In line ");
+        LintResult::findings(shift_line_numbers(&findings, self.preamble_lines()))
+    }
+
+    fn preamble_lines(&self) -> usize {
+        self.preamble().lines().count()
+    }
+}
+
+/// Splits the output of a linter that was run on multiple scripts into the
+/// output per script. Each script's output starts at a line that begins with
+/// its header and goes on up to the next header.
+fn split_output(output: &str, paths: &[&Path], header: impl Fn(&str) -> String) -> Vec<String> {
+    let headers: Vec<String> = paths.iter().map(|p| header(&p.to_string_lossy())).collect();
+    let mut outputs = vec![String::new(); paths.len()];
+    let mut current = None;
+    for line in output.lines() {
+        if let Some(i) = headers.iter().position(|h| line.starts_with(h.as_str())) {
+            current = Some(i);
+        }
+        if let Some(i) = current {
+            outputs[i].push_str(line);
+            outputs[i].push('\n');
+        }
+    }
+    outputs
+}
+
+/// Builds a line that assigns all the variables to themselves, so shellcheck
+/// knows that they are set without changing their values.
+fn shell_preamble(variables: &[String]) -> Option<String> {
+    let assignments = variables
+        .iter()
+        .filter(|v| {
+            !v.is_empty()
+                && !v.starts_with(|c: char| c.is_ascii_digit())
+                && v.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+        .map(|v| format!("{v}=\"${{{v}-}}\""))
+        .collect::<Vec<String>>();
+    if assignments.is_empty() {
+        return None;
+    }
+    // exporting prevents warnings about unused variables
+    Some(format!("export {}\n", assignments.join(" ")))
+}
+
+/// Corrects the line numbers of findings starting with "line 5:" or
+/// shellcheck's "In line 5:" for lines that were added in front of the script.
+fn shift_line_numbers(findings: &str, offset: usize) -> String {
+    if offset == 0 {
+        return findings.to_string();
     }
+    findings
+        .lines()
+        .map(|line| {
+            let (prefix, rest) = match line.strip_prefix("In line ") {
+                Some(rest) => ("In line ", rest),
+                None => ("line ", line.strip_prefix("line ").unwrap_or_default()),
+            };
+            let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+            match rest[..digits].parse::<usize>() {
+                Ok(n) if rest[digits..].starts_with(':') => {
+                    format!("{prefix}{}{}", n.saturating_sub(offset), &rest[digits..])
+                }
+                _ => line.to_string(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
 }
 
+#[derive(Debug)]
 pub struct Ruff;
 impl Display for Ruff {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -85,30 +903,85 @@ impl Display for Ruff {
 }
 
 impl LanguageHandler for Ruff {
+    fn executables(&self) -> Vec<&'static str> {
+        vec!["ruff"]
+    }
     fn file_extension(&self) -> &'static str {
         ".py"
     }
-    fn execute(&self, path: &Path) -> Result<LintResult, io::Error> {
-        let output = Command::new("ruff")
+    fn config_files(&self) -> &'static [&'static str] {
+        &[".ruff.toml", "ruff.toml", "pyproject.toml"]
+    }
+    fn execute(&self, path: &Path, settings: &RunSettings) -> Result<LintResult, io::Error> {
+        let output = Ruff::command(settings).arg(path).logged_output(settings)?;
+        check_exit("ruff", &output, 1)?;
+        Ok(Ruff::result(&String::from_utf8_lossy(&output.stdout), &path.to_string_lossy()))
+    }
+    fn batch_key(&self) -> Option<String> {
+        Some("ruff".to_string())
+    }
+    fn execute_batch(
+        &self,
+        paths: &[&Path],
+        settings: &RunSettings,
+    ) -> Result<Vec<LintResult>, io::Error> {
+        let output = Ruff::command(settings).args(paths).logged_output(settings)?;
+        check_exit("ruff", &output, 1)?;
+        let outputs =
+            split_output(&String::from_utf8_lossy(&output.stdout), paths, |p| format!("{p}:"));
+        Ok(paths.iter().zip(outputs).map(|(p, o)| Ruff::result(&o, &p.to_string_lossy())).collect())
+    }
+    fn supports_stdin(&self) -> bool {
+        true
+    }
+    fn execute_stdin(
+        &self,
+        content: &str,
+        file_name: &str,
+        settings: &RunSettings,
+    ) -> Result<LintResult, io::Error> {
+        let output = Ruff::command(settings)
+            .arg(format!("--stdin-filename={file_name}"))
+            .arg("-")
+            .logged_output_with_stdin(content, settings)?;
+        check_exit("ruff", &output, 1)?;
+        Ok(Ruff::result(&String::from_utf8_lossy(&output.stdout), file_name))
+    }
+    fn parse_findings(&self, output: &str) -> Vec<Finding> {
+        // ruff doesn't have severities, syntax errors are reported as errors anyway
+        parse_line_findings(output, Severity::Warning)
+    }
+}
+
+impl Ruff {
+    fn command(settings: &RunSettings) -> Command {
+        let mut command = Command::new("ruff");
+        command
             .arg("check")
             .arg("--output-format=full") // show context in source
             .arg("--no-cache")
-            .arg("--quiet") // don't print anything on success
-            .arg(path)
-            .output()?;
+            .arg("--quiet"); // don't print anything on success
+        if let Some(source) = &settings.config_source {
+            source.apply(&mut command, Ruff.config_files(), "--isolated");
+        }
+        command
+    }
+
+    fn result(output: &str, script: &str) -> LintResult {
         let mut valid_lines: Vec<String> = vec![];
-        for line in String::from_utf8_lossy(&output.stdout).trim().lines() {
+        for line in output.trim().lines() {
             // breaks on "Found x error."
             if line.starts_with("Found ") {
                 break;
             }
 
-            valid_lines.push(line.replace(&format!("{}:", path.to_string_lossy()), "line "));
+            valid_lines.push(line.replace(&format!("{script}:"), "line "));
         }
-        Ok(LintResult::findings(valid_lines.join("\n").trim().to_string()))
+        LintResult::findings(valid_lines.join("\n").trim().to_string())
     }
 }
 
+#[derive(Debug)]
 pub struct Rubocop;
 impl Display for Rubocop {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -117,26 +990,179 @@ impl Display for Rubocop {
 }
 
 impl LanguageHandler for Rubocop {
+    fn executables(&self) -> Vec<&'static str> {
+        vec!["rubocop"]
+    }
     fn file_extension(&self) -> &'static str {
         ".rb"
     }
-    fn execute(&self, path: &Path) -> Result<LintResult, io::Error> {
-        let output = Command::new("rubocop")
-            .arg("--format=clang")
-            .arg("--display-style-guide")
-            .arg(path)
-            .output()?;
-        let findings = String::from_utf8_lossy(&output.stdout)
+    fn config_files(&self) -> &'static [&'static str] {
+        &[".rubocop.yml"]
+    }
+    fn execute(&self, path: &Path, settings: &RunSettings) -> Result<LintResult, io::Error> {
+        let output = settings.daemon_output("rubocop", 1, |server| {
+            Rubocop::command(server, settings).arg(path).logged_output(settings)
+        })?;
+        Ok(Rubocop::result(&String::from_utf8_lossy(&output.stdout), &path.to_string_lossy()))
+    }
+    fn batch_key(&self) -> Option<String> {
+        Some("rubocop".to_string())
+    }
+    fn execute_batch(
+        &self,
+        paths: &[&Path],
+        settings: &RunSettings,
+    ) -> Result<Vec<LintResult>, io::Error> {
+        let output = settings.daemon_output("rubocop", 1, |server| {
+            Rubocop::command(server, settings).args(paths).logged_output(settings)
+        })?;
+        let outputs =
+            split_output(&String::from_utf8_lossy(&output.stdout), paths, |p| format!("{p}:"));
+        Ok(paths
+            .iter()
+            .zip(outputs)
+            .map(|(p, o)| Rubocop::result(&o, &p.to_string_lossy()))
+            .collect())
+    }
+    fn supports_stdin(&self) -> bool {
+        true
+    }
+    fn execute_stdin(
+        &self,
+        content: &str,
+        file_name: &str,
+        settings: &RunSettings,
+    ) -> Result<LintResult, io::Error> {
+        let output = settings.daemon_output("rubocop", 1, |server| {
+            Rubocop::command(server, settings)
+                .arg("--stdin")
+                .arg(file_name)
+                .logged_output_with_stdin(content, settings)
+        })?;
+        Ok(Rubocop::result(&String::from_utf8_lossy(&output.stdout), file_name))
+    }
+    fn parse_findings(&self, output: &str) -> Vec<Finding> {
+        // the severity is given as a single letter in front of each message
+        parse_line_findings(output, Severity::Style)
+    }
+}
+
+impl Rubocop {
+    /// The server keeps rubocop loaded between runs, since loading it takes
+    /// much longer than linting the scripts.
+    fn command(server: bool, settings: &RunSettings) -> Command {
+        let mut command = Command::new("rubocop");
+        if server {
+            command.arg("--server");
+        }
+        command.arg("--format=clang").arg("--display-style-guide");
+        if let Some(source) = &settings.config_source {
+            source.apply(&mut command, Rubocop.config_files(), "--force-default-config");
+        }
+        command
+    }
+
+    fn result(output: &str, script: &str) -> LintResult {
+        let findings = output
             .lines()
-            .filter(|l| !l.contains("1 file inspected"))
+            // drops the summary like "3 files inspected, 1 offense detected"
+            .filter(|l| !l.contains(" inspected, "))
             .collect::<Vec<&str>>()
             .join("\n")
+            .trim()
+            .replace(&format!("{script}:"), "line ");
+        LintResult::findings(findings)
+    }
+}
+
+#[derive(Debug)]
+pub struct Shfmt;
+impl Display for Shfmt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "shfmt")
+    }
+}
+
+impl LanguageHandler for Shfmt {
+    fn executables(&self) -> Vec<&'static str> {
+        vec!["shfmt"]
+    }
+    fn file_extension(&self) -> &'static str {
+        ".sh"
+    }
+    fn execute(&self, path: &Path, settings: &RunSettings) -> Result<LintResult, io::Error> {
+        // prints a diff of the needed formatting changes
+        let output = Command::new("shfmt").arg("-d").arg(path).logged_output(settings)?;
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let findings = String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .replace(path.to_string_lossy().as_ref(), &file_name);
+        Ok(LintResult::findings(findings))
+    }
+    fn parse_findings(&self, output: &str) -> Vec<Finding> {
+        // the diff only contains formatting changes
+        vec![Finding::unparsed(output, Severity::Style)]
+    }
+}
+
+#[derive(Debug)]
+pub struct Mypy;
+impl Display for Mypy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "mypy")
+    }
+}
+
+impl LanguageHandler for Mypy {
+    fn executables(&self) -> Vec<&'static str> {
+        vec!["mypy"]
+    }
+    fn file_extension(&self) -> &'static str {
+        ".py"
+    }
+    fn execute(&self, path: &Path, settings: &RunSettings) -> Result<LintResult, io::Error> {
+        let output = Command::new("mypy")
+            .arg("--strict")
+            .arg("--no-error-summary")
+            .arg("--no-color-output")
+            .arg("--no-incremental") // don't leave a cache dir behind
+            .arg(path)
+            .logged_output(settings)?;
+        check_exit("mypy", &output, 1)?;
+        let findings = String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .replace(&format!("{}:", path.to_string_lossy()), "line ");
+        Ok(LintResult::findings(findings))
+    }
+}
+
+#[derive(Debug)]
+pub struct RubySyntax;
+impl Display for RubySyntax {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ruby")
+    }
+}
+
+impl LanguageHandler for RubySyntax {
+    fn executables(&self) -> Vec<&'static str> {
+        vec!["ruby"]
+    }
+    fn file_extension(&self) -> &'static str {
+        ".rb"
+    }
+    fn execute(&self, path: &Path, settings: &RunSettings) -> Result<LintResult, io::Error> {
+        // ruby prints "Syntax OK" to stdout and the errors to stderr
+        let output = Command::new("ruby").arg("-c").arg(path).logged_output(settings)?;
+        check_exit("ruby", &output, 1)?;
+        let findings = String::from_utf8_lossy(&output.stderr)
             .trim()
             .replace(&format!("{}:", path.to_string_lossy()), "line ");
         Ok(LintResult::findings(findings))
     }
 }
 
+#[derive(Debug)]
 pub struct Nushell;
 impl Display for Nushell {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -145,18 +1171,1035 @@ impl Display for Nushell {
 }
 
 impl LanguageHandler for Nushell {
+    fn executables(&self) -> Vec<&'static str> {
+        vec!["nu"]
+    }
     fn file_extension(&self) -> &'static str {
         ".nu"
     }
-    fn execute(&self, path: &Path) -> Result<LintResult, io::Error> {
+    fn execute(&self, path: &Path, settings: &RunSettings) -> Result<LintResult, io::Error> {
         let output = Command::new("nu")
             .arg("-c")
-            .arg(&format!(
+            .arg(format!(
                 "if not (nu-check {}) {{ print 'file could not be parsed by nu-check' }}",
                 path.to_string_lossy()
             ))
-            .output()?;
+            .logged_output(settings)?;
+        let findings = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        Ok(LintResult::findings(findings))
+    }
+}
+
+#[derive(Debug)]
+pub struct PowerShell;
+impl Display for PowerShell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "powershell")
+    }
+}
+
+impl LanguageHandler for PowerShell {
+    fn executables(&self) -> Vec<&'static str> {
+        vec!["pwsh"]
+    }
+    fn file_extension(&self) -> &'static str {
+        ".ps1"
+    }
+    fn execute(&self, path: &Path, settings: &RunSettings) -> Result<LintResult, io::Error> {
+        // single quoted strings in powershell only need the quote itself escaped
+        let path = path.to_string_lossy().replace('\'', "''");
+        // PSScriptAnalyzer is an optional module, so fall back to only parsing
+        // the script if it's not installed
+        let script = format!(
+            "if (Get-Module -ListAvailable -Name PSScriptAnalyzer) {{
+                Invoke-ScriptAnalyzer -Path '{path}' | ForEach-Object {{
+                    \"line $($_.Line):$($_.Column): $($_.RuleName) ($($_.Severity)): $($_.Message)\"
+                }}
+            }} else {{
+                $errors = $null
+                [System.Management.Automation.Language.Parser]::ParseFile('{path}', [ref]$null, [ref]$errors) | Out-Null
+                $errors | ForEach-Object {{
+                    \"line $($_.Extent.StartLineNumber):$($_.Extent.StartColumnNumber): $($_.ErrorId): $($_.Message)\"
+                }}
+            }}"
+        );
+        let output = Command::new("pwsh")
+            .arg("-NoProfile")
+            .arg("-NonInteractive")
+            .arg("-Command")
+            .arg(script)
+            .logged_output(settings)?;
+        // the findings are printed by the script, which only fails on errors
+        check_exit("pwsh", &output, 0)?;
         let findings = String::from_utf8_lossy(&output.stdout).trim().to_string();
         Ok(LintResult::findings(findings))
     }
+    fn parse_findings(&self, output: &str) -> Vec<Finding> {
+        parse_line_findings(output, Severity::Warning)
+    }
+}
+
+const FISH_INDENT_MESSAGE: &str = "script is not formatted according to fish_indent";
+
+#[derive(Debug)]
+pub struct Fish;
+impl Display for Fish {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "fish")
+    }
+}
+
+impl LanguageHandler for Fish {
+    fn executables(&self) -> Vec<&'static str> {
+        vec!["fish", "fish_indent"]
+    }
+    fn file_extension(&self) -> &'static str {
+        ".fish"
+    }
+    fn execute(&self, path: &Path, settings: &RunSettings) -> Result<LintResult, io::Error> {
+        // fish reports syntax errors on stderr
+        let output = Command::new("fish").arg("--no-execute").arg(path).logged_output(settings)?;
+        // syntax errors exit with 127, like unknown commands
+        check_exit("fish", &output, 127)?;
+        let mut findings = String::from_utf8_lossy(&output.stderr)
+            .trim()
+            .replace(&format!("{} ", path.to_string_lossy()), "");
+
+        // fish_indent is optional, so it's just skipped if it's not available
+        match Command::new("fish_indent").arg("--check").arg(path).logged_output(settings) {
+            Ok(output) if !output.status.success() => {
+                check_exit("fish_indent", &output, 1)?;
+                if !findings.is_empty() {
+                    findings.push('\n');
+                }
+                findings.push_str(FISH_INDENT_MESSAGE);
+            }
+            Ok(_) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        Ok(LintResult::findings(findings))
+    }
+    fn parse_findings(&self, output: &str) -> Vec<Finding> {
+        let (syntax_errors, unformatted) = match output.strip_suffix(FISH_INDENT_MESSAGE) {
+            Some(rest) => (rest.trim(), true),
+            None => (output, false),
+        };
+        let mut findings = vec![];
+        if !syntax_errors.is_empty() {
+            findings.push(Finding::unparsed(syntax_errors, Severity::Error));
+        }
+        if unformatted {
+            findings.push(Finding::unparsed(FISH_INDENT_MESSAGE, Severity::Style));
+        }
+        findings
+    }
+}
+
+#[derive(Debug)]
+pub struct Zsh {
+    pub shellcheck: bool,
+    /// Variables that are set by mask, only needed for shellcheck.
+    pub variables: Vec<String>,
+}
+impl Display for Zsh {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "zsh")
+    }
+}
+
+impl LanguageHandler for Zsh {
+    fn executables(&self) -> Vec<&'static str> {
+        if self.shellcheck {
+            vec!["zsh", "shellcheck"]
+        } else {
+            vec!["zsh"]
+        }
+    }
+    fn file_extension(&self) -> &'static str {
+        ".zsh"
+    }
+    fn execute(&self, path: &Path, settings: &RunSettings) -> Result<LintResult, io::Error> {
+        // zsh reports syntax errors on stderr
+        let output = Command::new("zsh").arg("-n").arg(path).logged_output(settings)?;
+        let mut findings = String::from_utf8_lossy(&output.stderr)
+            .trim()
+            .replace(&format!("{}:", path.to_string_lossy()), "line ");
+
+        if self.shellcheck {
+            let output =
+                Command::new("shellcheck").arg("--shell=bash").arg(path).logged_output(settings)?;
+            check_exit("shellcheck", &output, 1)?;
+            let shellcheck_findings = String::from_utf8_lossy(&output.stdout)
+                .trim()
+                .replace(&format!("{} ", path.to_string_lossy()), "");
+            if !findings.is_empty() && !shellcheck_findings.is_empty() {
+                findings.push_str("\n\n");
+            }
+            findings.push_str(&shellcheck_findings);
+        }
+        Ok(LintResult::findings(shift_line_numbers(&findings, self.preamble_lines())))
+    }
+    fn parse_findings(&self, output: &str) -> Vec<Finding> {
+        let mut findings = parse_line_findings(output, Severity::Error);
+        findings.extend(parse_shellcheck_findings(output));
+        findings
+    }
+    fn preamble(&self) -> String {
+        if !self.shellcheck {
+            return String::new();
+        }
+        shell_preamble(&self.variables).unwrap_or_default()
+    }
+}
+
+impl Zsh {
+    fn preamble_lines(&self) -> usize {
+        self.preamble().lines().count()
+    }
+}
+
+#[derive(Debug)]
+pub struct JavaScript {
+    pub linter: JavaScriptLinter,
+}
+impl Display for JavaScript {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.linter {
+            JavaScriptLinter::Biome => write!(f, "biome"),
+            JavaScriptLinter::Eslint => write!(f, "eslint"),
+        }
+    }
+}
+
+impl LanguageHandler for JavaScript {
+    fn executables(&self) -> Vec<&'static str> {
+        match self.linter {
+            JavaScriptLinter::Biome => vec!["biome"],
+            JavaScriptLinter::Eslint => vec!["eslint"],
+        }
+    }
+    fn file_extension(&self) -> &'static str {
+        ".js"
+    }
+    fn execute(&self, path: &Path, settings: &RunSettings) -> Result<LintResult, io::Error> {
+        let output = match self.linter {
+            JavaScriptLinter::Biome => Command::new("biome")
+                .arg("check")
+                .arg("--colors=off")
+                .arg(path)
+                .logged_output(settings)?,
+            JavaScriptLinter::Eslint => {
+                let output = Command::new("eslint")
+                    .arg("--format=unix")
+                    .arg(path)
+                    .logged_output(settings)?;
+                check_exit("eslint", &output, 1)?;
+                output
+            }
+        };
+        // biome prints its diagnostics to stderr, eslint to stdout
+        let mut raw = String::from_utf8_lossy(&output.stdout).to_string();
+        raw.push_str(&String::from_utf8_lossy(&output.stderr));
+        let findings = raw
+            .lines()
+            // drops the summaries like "Checked 1 file in 2ms." or "3 problems"
+            .filter(|l| {
+                !l.starts_with("Checked ")
+                    && !l.starts_with("Found ")
+                    && !l.ends_with(" problem")
+                    && !l.ends_with(" problems")
+            })
+            .collect::<Vec<&str>>()
+            .join("\n")
+            .trim()
+            .replace(&format!("{}:", path.to_string_lossy()), "line ");
+        Ok(LintResult::findings(findings))
+    }
+    fn parse_findings(&self, output: &str) -> Vec<Finding> {
+        parse_line_findings(output, Severity::Warning)
+    }
+}
+
+#[derive(Debug)]
+pub struct Deno {
+    pub check: bool,
+}
+impl Display for Deno {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "deno")
+    }
+}
+
+impl LanguageHandler for Deno {
+    fn executables(&self) -> Vec<&'static str> {
+        vec!["deno"]
+    }
+    fn file_extension(&self) -> &'static str {
+        ".ts"
+    }
+    fn execute(&self, path: &Path, settings: &RunSettings) -> Result<LintResult, io::Error> {
+        let mut subcommands = vec!["lint"];
+        if self.check {
+            subcommands.push("check");
+        }
+
+        let mut results: Vec<String> = vec![];
+        for subcommand in subcommands {
+            let output = Command::new("deno")
+                .arg(subcommand)
+                .arg(path)
+                .env("NO_COLOR", "1")
+                .logged_output(settings)?;
+            check_exit("deno", &output, 1)?;
+            // deno prints its diagnostics to stderr
+            let mut raw = String::from_utf8_lossy(&output.stdout).to_string();
+            raw.push_str(&String::from_utf8_lossy(&output.stderr));
+            let findings = raw
+                .lines()
+                // drops the progress and summary lines like "Checked 1 file"
+                .filter(|l| {
+                    !l.starts_with("Check ")
+                        && !l.starts_with("Checked ")
+                        && !l.starts_with("Found ")
+                })
+                .collect::<Vec<&str>>()
+                .join("\n")
+                .trim()
+                .replace(&format!("file://{}:", path.to_string_lossy()), "line ")
+                .replace(&format!("{}:", path.to_string_lossy()), "line ");
+            if !findings.is_empty() {
+                results.push(findings);
+            }
+        }
+        Ok(LintResult::findings(results.join("\n\n")))
+    }
+}
+
+#[derive(Debug)]
+pub struct Lua;
+impl Display for Lua {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "luacheck")
+    }
+}
+
+impl LanguageHandler for Lua {
+    fn executables(&self) -> Vec<&'static str> {
+        vec!["luacheck"]
+    }
+    fn file_extension(&self) -> &'static str {
+        ".lua"
+    }
+    fn execute(&self, path: &Path, settings: &RunSettings) -> Result<LintResult, io::Error> {
+        let output = Command::new("luacheck")
+            .arg("--no-color")
+            .arg("--codes") // show the warning codes, e.g. (W211)
+            .arg("--formatter=plain")
+            .arg(path)
+            .logged_output(settings)?;
+        check_exit("luacheck", &output, 2)?;
+        let findings = String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .replace(&format!("{}:", path.to_string_lossy()), "line ");
+        Ok(LintResult::findings(findings))
+    }
+    fn parse_findings(&self, output: &str) -> Vec<Finding> {
+        parse_line_findings(output, Severity::Warning)
+    }
+}
+
+#[derive(Debug)]
+pub struct Perl;
+impl Display for Perl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "perlcritic")
+    }
+}
+
+impl LanguageHandler for Perl {
+    fn executables(&self) -> Vec<&'static str> {
+        vec!["perlcritic", "perl"]
+    }
+    fn file_extension(&self) -> &'static str {
+        ".pl"
+    }
+    fn execute(&self, path: &Path, settings: &RunSettings) -> Result<LintResult, io::Error> {
+        let output = match Command::new("perlcritic")
+            .arg("--verbose")
+            .arg("line %l:%c %m [%p]\n")
+            .arg(path)
+            .logged_output(settings)
+        {
+            // 2 is for the findings and 1 for perlcritic's own errors
+            Ok(output) => {
+                check_exit_codes("perlcritic", &output, &[0, 2])?;
+                output
+            }
+            // perl itself is way more common than perlcritic, so at least
+            // check the syntax in that case
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let output = Command::new("perl").arg("-c").arg(path).logged_output(settings)?;
+                // compilation errors exit with 255
+                check_exit_codes("perl", &output, &[0, 255])?;
+                // perl reports the syntax check results on stderr
+                let location = format!(" at {} line ", path.to_string_lossy());
+                let findings = String::from_utf8_lossy(&output.stderr)
+                    .lines()
+                    .filter(|l| {
+                        !l.ends_with(" syntax OK") && !l.ends_with(" had compilation errors.")
+                    })
+                    // like the other findings, e.g. "line 3: syntax error, near ..."
+                    .map(|l| match l.split_once(&location) {
+                        Some((message, rest)) => {
+                            let end =
+                                rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+                            let (line, near) = rest.split_at(end);
+                            format!("line {line}: {message}{}", near.trim_end_matches('.'))
+                        }
+                        None => l.to_string(),
+                    })
+                    .collect::<Vec<String>>()
+                    .join("\n")
+                    .trim()
+                    .to_string();
+                return Ok(LintResult::findings(findings));
+            }
+            Err(e) => return Err(e),
+        };
+        let findings = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter(|l| !l.ends_with(" source OK"))
+            .collect::<Vec<&str>>()
+            .join("\n")
+            .trim()
+            .to_string();
+        Ok(LintResult::findings(findings))
+    }
+    fn parse_findings(&self, output: &str) -> Vec<Finding> {
+        parse_line_findings(output, Severity::Warning)
+    }
+}
+
+#[derive(Debug)]
+pub struct Php {
+    /// Whether the opening tag is added in a line before the script, since
+    /// mask runs the source as code, but in a file it would be plain text.
+    pub opening_tag: bool,
+}
+impl Display for Php {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "php")
+    }
+}
+
+impl LanguageHandler for Php {
+    fn executables(&self) -> Vec<&'static str> {
+        vec!["php", "phpcs"]
+    }
+    fn file_extension(&self) -> &'static str {
+        ".php"
+    }
+    fn execute(&self, path: &Path, settings: &RunSettings) -> Result<LintResult, io::Error> {
+        let output = Command::new("php").arg("-l").arg(path).logged_output(settings)?;
+        // parse errors exit with 255
+        check_exit_codes("php", &output, &[0, 255])?;
+        let mut findings = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter(|l| {
+                !l.starts_with("No syntax errors detected") && !l.starts_with("Errors parsing")
+            })
+            .collect::<Vec<&str>>()
+            .join("\n")
+            .trim()
+            .replace(&format!(" in {} on line ", path.to_string_lossy()), " on line ")
+            .lines()
+            // like the other findings, e.g. "line 3: Parse error: ..."
+            .map(|l| match l.rsplit_once(" on line ") {
+                Some((message, line)) if line.parse::<usize>().is_ok() => {
+                    format!("line {line}: {message}")
+                }
+                _ => l.to_string(),
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        // phpcs is optional, so only the syntax check is done if it's not available
+        match Command::new("phpcs")
+            .arg("--report=emacs")
+            .arg("-q")
+            .arg(path)
+            .logged_output(settings)
+        {
+            Ok(output) => {
+                // 1 and 2 are for the findings, 3 for processing errors
+                check_exit("phpcs", &output, 2)?;
+                let style_findings = String::from_utf8_lossy(&output.stdout)
+                    .trim()
+                    .replace(&format!("{}:", path.to_string_lossy()), "line ");
+                if !findings.is_empty() && !style_findings.is_empty() {
+                    findings.push_str("\n\n");
+                }
+                findings.push_str(&style_findings);
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        Ok(LintResult::findings(shift_line_numbers(&findings, self.opening_tag as usize)))
+    }
+    fn content(&self, script: &Script) -> Result<String, io::Error> {
+        if !self.opening_tag {
+            return Ok(script.source.clone());
+        }
+        let mut res = "<?php\n".to_string();
+        res.push_str(&script.source);
+        Ok(res)
+    }
+}
+
+#[derive(Debug)]
+pub struct Sql {
+    pub dialect: String,
+}
+impl Display for Sql {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "sqlfluff")
+    }
+}
+
+impl LanguageHandler for Sql {
+    fn executables(&self) -> Vec<&'static str> {
+        vec!["sqlfluff"]
+    }
+    fn file_extension(&self) -> &'static str {
+        ".sql"
+    }
+    fn execute(&self, path: &Path, settings: &RunSettings) -> Result<LintResult, io::Error> {
+        let output = Command::new("sqlfluff")
+            .arg("lint")
+            .arg("--format=github-annotation-native")
+            .arg(format!("--dialect={}", self.dialect))
+            .arg(path)
+            .logged_output(settings)?;
+        check_exit("sqlfluff", &output, 1)?;
+        let findings = String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .lines()
+            .map(format_github_annotation)
+            .collect::<Vec<String>>()
+            .join("\n");
+        Ok(LintResult::findings(findings))
+    }
+    fn parse_findings(&self, output: &str) -> Vec<Finding> {
+        parse_line_findings(output, Severity::Warning)
+    }
+}
+
+/// Turns an annotation like `::error title=SQLFluff,file=x.sql,line=1,col=5::LT01: msg`
+/// into `line 1:5: LT01: msg`. Anything else is returned as is.
+fn format_github_annotation(line: &str) -> String {
+    let Some((properties, message)) = line.strip_prefix("::").and_then(|l| l.split_once("::"))
+    else {
+        return line.to_string();
+    };
+    let property = |key: &str| {
+        properties
+            .split([' ', ','])
+            .find_map(|p| p.strip_prefix(key).and_then(|p| p.strip_prefix('=')))
+    };
+    match (property("line"), property("col")) {
+        (Some(line), Some(col)) => format!("line {line}:{col}: {message}"),
+        (Some(line), None) => format!("line {line}: {message}"),
+        _ => message.to_string(),
+    }
+}
+
+#[derive(Debug)]
+pub struct Rlang;
+impl Display for Rlang {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "lintr")
+    }
+}
+
+impl LanguageHandler for Rlang {
+    fn executables(&self) -> Vec<&'static str> {
+        vec!["Rscript"]
+    }
+    fn file_extension(&self) -> &'static str {
+        ".R"
+    }
+    fn execute(&self, path: &Path, settings: &RunSettings) -> Result<LintResult, io::Error> {
+        let path = path.to_string_lossy();
+        let r_path = path.replace('\\', "\\\\").replace('"', "\\\"");
+        let output = Command::new("Rscript")
+            .arg("-e")
+            .arg(format!("print(lintr::lint(\"{r_path}\"))"))
+            .logged_output(settings)?;
+        // e.g. if lintr isn't installed, the findings don't change the exit code
+        check_exit("lintr", &output, 0)?;
+        let findings =
+            String::from_utf8_lossy(&output.stdout).trim().replace(&format!("{path}:"), "line ");
+        Ok(LintResult::findings(findings))
+    }
+    fn parse_findings(&self, output: &str) -> Vec<Finding> {
+        parse_line_findings(output, Severity::Warning)
+    }
+}
+
+#[derive(Debug)]
+pub struct Batch;
+impl Display for Batch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "batch")
+    }
+}
+
+impl LanguageHandler for Batch {
+    fn file_extension(&self) -> &'static str {
+        ".bat"
+    }
+    fn execute(&self, path: &Path, _: &RunSettings) -> Result<LintResult, io::Error> {
+        let source = fs::read_to_string(path)?;
+        Ok(LintResult::findings(check_batch_syntax(&source).join("\n")))
+    }
+}
+
+/// Minimal syntax check for batch scripts since there is no widespread linter
+/// for them. It only finds unbalanced parentheses and jumps to undefined labels.
+fn check_batch_syntax(source: &str) -> Vec<String> {
+    let mut findings: Vec<(usize, String)> = vec![];
+    let mut open_parens: Vec<usize> = vec![];
+    let mut labels = HashSet::new();
+    let mut jumps: Vec<(usize, String)> = vec![];
+
+    for (i, line) in source.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line.trim_start().trim_start_matches('@').to_lowercase();
+        if line.starts_with("::") || line == "rem" || line.starts_with("rem ") {
+            continue;
+        }
+        if let Some(label) = line.strip_prefix(':') {
+            labels.insert(label.split_whitespace().next().unwrap_or_default().to_string());
+            continue;
+        }
+
+        let mut in_quotes = false;
+        let mut escaped = false;
+        // the operators become separate words to tell the command words apart
+        let mut spaced = String::new();
+        for c in line.chars() {
+            let operator = !escaped && !in_quotes && matches!(c, '(' | ')' | '&' | '|');
+            if operator {
+                spaced.extend([' ', c, ' ']);
+            } else {
+                spaced.push(c);
+            }
+            match c {
+                _ if escaped => escaped = false,
+                '^' => escaped = true,
+                '"' => in_quotes = !in_quotes,
+                '(' if !in_quotes => open_parens.push(line_number),
+                ')' if !in_quotes && open_parens.pop().is_none() => {
+                    findings.push((line_number, "unmatched ')'".to_string()));
+                }
+                _ => {}
+            }
+        }
+
+        let words: Vec<&str> = spaced.split_whitespace().collect();
+        // only a goto or call in the place of a command jumps, not e.g. an echoed one
+        let mut command = 0;
+        for (j, word) in words.iter().enumerate() {
+            match *word {
+                "&" | "|" | "(" | ")" => command = j + 1,
+                "do" | "else" if j == command => command = j + 1,
+                "if" if j == command => command = j + 1 + batch_condition_len(&words[j + 1..]),
+                _ => {}
+            }
+            if j != command {
+                continue;
+            }
+            let target = match *word {
+                "goto" => words.get(j + 1).map(|t| t.trim_start_matches(':')),
+                "call" => words.get(j + 1).and_then(|t| t.strip_prefix(':')),
+                _ => word.strip_prefix("goto:"),
+            };
+            // dynamic targets like %target% can't be checked
+            if let Some(target) = target.filter(|t| !t.contains(['%', '!'])) {
+                jumps.push((line_number, target.to_string()));
+            }
+        }
+    }
+
+    for line_number in open_parens {
+        findings.push((line_number, "unmatched '('".to_string()));
+    }
+    for (line_number, label) in jumps {
+        // :eof is a builtin label to exit the script or subroutine
+        if label != "eof" && !labels.contains(&label) {
+            findings.push((line_number, format!("label '{label}' is not defined")));
+        }
+    }
+    findings.sort_by_key(|(line_number, _)| *line_number);
+    findings.into_iter().map(|(line_number, msg)| format!("line {line_number}: {msg}")).collect()
+}
+
+/// The number of words of the condition of an if, e.g. `not exist x` or `%a% == b`.
+fn batch_condition_len(words: &[&str]) -> usize {
+    let flags = words.iter().take_while(|w| matches!(**w, "/i" | "not")).count();
+    let condition = match words.get(flags) {
+        Some(&"exist" | &"defined" | &"errorlevel" | &"cmdextversion") => 2,
+        Some(word) if word.contains("==") && !word.ends_with("==") => 1,
+        _ => 3,
+    };
+    flags + condition
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use std::cell::RefCell;
+
+    #[rstest]
+    #[case(
+        "::error title=SQLFluff,file=/tmp/x.sql,line=1,col=5,endLine=1,endColumn=6::LT01: Expected only single space.",
+        "line 1:5: LT01: Expected only single space."
+    )]
+    #[case(
+        "::warning title=SQLFluff,file=/tmp/x.sql,line=3::PRS: Unparsable",
+        "line 3: PRS: Unparsable"
+    )]
+    #[case("some other output", "some other output")]
+    fn test_format_github_annotation(#[case] line: &str, #[case] expected: &str) {
+        assert_eq!(format_github_annotation(line), expected);
+    }
+
+    #[rstest]
+    #[case("if exist x (\n  echo \"(\"\n) else (\n  echo ^)\n)\n", vec![])]
+    #[case("if exist x (\n  echo a\n", vec!["line 1: unmatched '('"])]
+    #[case("echo a)\nrem )\n:: )", vec!["line 1: unmatched ')'"])]
+    #[case("goto end\ncall :sub\ngoto :eof\n:END\n", vec!["line 2: label 'sub' is not defined"])]
+    #[case("goto %target%\ngoto:missing", vec!["line 2: label 'missing' is not defined"])]
+    #[case("echo please goto settings\nrem call :sub\necho ^& goto x", vec![])]
+    #[case(
+        "if not exist x goto a\nif %b%==1 call :b\ncd x && goto c\nfor %%f in (*) do goto d",
+        vec![
+            "line 1: label 'a' is not defined",
+            "line 2: label 'b' is not defined",
+            "line 3: label 'c' is not defined",
+            "line 4: label 'd' is not defined",
+        ]
+    )]
+    #[case("if x == y (goto e) else goto f", vec!["line 1: label 'e' is not defined", "line 1: label 'f' is not defined"])]
+    fn test_check_batch_syntax(#[case] source: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(check_batch_syntax(source), expected);
+    }
+
+    #[test]
+    fn test_merge_lint_results() {
+        let merged = LintResult::merge(vec![
+            ("shellcheck".to_string(), LintResult::findings("SC2086".to_string())),
+            ("shfmt".to_string(), LintResult::findings(String::new())),
+        ]);
+        assert_eq!(merged.message, "SC2086");
+        assert!(matches!(merged.result_type, LintResultType::Findings));
+
+        let merged = LintResult::merge(vec![
+            ("ruff".to_string(), LintResult::findings("E703".to_string())),
+            ("mypy".to_string(), LintResult::findings("error: missing return".to_string())),
+        ]);
+        assert_eq!(merged.message, "[ruff]\nE703\n\n[mypy]\nerror: missing return");
+    }
+
+    #[rstest]
+    #[case("#!/usr/bin/env python3\nprint()", Some("python"))]
+    #[case("#!/bin/bash -e\necho", Some("bash"))]
+    #[case("#!/usr/bin/env -S ruby3.2 -w\nputs", Some("ruby"))]
+    #[case("echo", None)]
+    fn test_shebang_executor(#[case] source: &str, #[case] expected: Option<&str>) {
+        assert_eq!(shebang_executor(source).map(|e| e.program).as_deref(), expected);
+    }
+
+    #[rstest]
+    #[case("bash -e", "bash", &["-e"])]
+    #[case("bash -eu -o pipefail --norc", "bash", &["-eu", "-o", "pipefail"])]
+    #[case("sh -c", "sh", &[])]
+    #[case("/usr/bin/ksh93 -e -i", "ksh", &["-e"])]
+    fn test_parse_executor(
+        #[case] executor: &str,
+        #[case] program: &str,
+        #[case] options: &[&str],
+    ) {
+        let executor = parse_executor(executor).unwrap();
+        assert_eq!(executor.program, program);
+        assert_eq!(executor.shell_options(), options);
+    }
+
+    #[rstest]
+    #[case("ShellCheck - shell script analysis tool\nversion: 0.9.0\nlicense: GPLv3", &[0, 9])]
+    #[case("ruff 0.4.10", &[0, 4, 10])]
+    #[case("1.60.2\n", &[1, 60, 2])]
+    #[case("nushell v0.95.0-nightly.3", &[0, 95])]
+    #[case("0.4", &[0, 4])]
+    fn test_parse_version(#[case] output: &str, #[case] expected: &[u64]) {
+        assert_eq!(parse_version(output).as_deref(), Some(expected));
+        assert_eq!(parse_version("unknown"), None);
+    }
+
+    #[test]
+    fn test_shell_preamble() {
+        let variables = vec!["who".to_string(), "verbose".to_string(), "not-valid".to_string()];
+        assert_eq!(
+            shell_preamble(&variables).as_deref(),
+            Some("export who=\"${who-}\" verbose=\"${verbose-}\"\n")
+        );
+        assert_eq!(shell_preamble(&[]), None);
+        let shellcheck = Shellcheck {
+            shell: "bash".to_string(),
+            variables: vec!["who".to_string()],
+            options: vec!["-e".to_string()],
+        };
+        assert_eq!(shellcheck.preamble(), "export who=\"${who-}\"\nset -e\n");
+        assert_eq!(shellcheck.preamble_lines(), 2);
+        assert_eq!(shift_line_numbers("In line 3:\nmkdir $x", 1), "In line 2:\nmkdir $x");
+        assert_eq!(
+            shift_line_numbers("line 3: parse error\nline 12:4: x\nline x", 1),
+            "line 2: parse error\nline 11:4: x\nline x"
+        );
+    }
+
+    /// Settings with a PATH that only has the executables, which run the
+    /// shell scripts instead. The dir has to be kept until they're run.
+    fn fake_linters(executables: &[(&str, &str)]) -> (tempfile::TempDir, RunSettings) {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        for (executable, script) in executables {
+            let path = dir.path().join(executable);
+            fs::write(&path, format!("#!/bin/sh\n{script}\n")).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        let path = dir.path().to_string_lossy().to_string();
+        let environment = Environment {
+            vars: BTreeMap::from([("PATH".to_string(), path)]),
+            ..Default::default()
+        };
+        (dir, RunSettings { environment: Some(environment), ..RunSettings::default() })
+    }
+
+    #[test]
+    fn test_rlang_exit() {
+        let script = "echo \"Error: there is no package called 'lintr'\" >&2; exit 1";
+        let (_dir, settings) = fake_linters(&[("Rscript", script)]);
+        let error = Rlang.execute(Path::new("build.R"), &settings);
+        assert!(error.unwrap_err().to_string().contains("there is no package called 'lintr'"));
+        let script = "echo \"build.R:1:1: style: trailing whitespace\"";
+        let (_dir, settings) = fake_linters(&[("Rscript", script)]);
+        let result = Rlang.execute(Path::new("build.R"), &settings).unwrap();
+        assert_eq!(result.message, "line 1:1: style: trailing whitespace");
+    }
+
+    #[rstest]
+    #[case(&RubySyntax, "ruby", "exit 2")]
+    #[case(&PowerShell, "pwsh", "exit 1")]
+    #[case(&Fish, "fish", "kill -9 $$")]
+    #[case(&Deno { check: false }, "deno", "exit 2")]
+    #[case(&Perl, "perlcritic", "exit 1")]
+    #[case(&Perl, "perl", "exit 2")]
+    #[case(&Php { opening_tag: false }, "php", "exit 1")]
+    fn test_tool_errors(
+        #[case] handler: &dyn LanguageHandler,
+        #[case] executable: &str,
+        #[case] script: &str,
+    ) {
+        let script = format!("echo 'internal error' >&2; {script}");
+        let (_dir, settings) = fake_linters(&[(executable, &script)]);
+        let error = handler.execute(Path::new("build"), &settings).unwrap_err();
+        assert!(error.to_string().starts_with(&format!("{executable} failed with ")));
+    }
+
+    #[test]
+    fn test_perl_syntax_check() {
+        let script = "echo 'syntax error at build.pl line 3, near \"= ;\"' >&2
+            echo 'Global symbol \"$y\" requires explicit package name at build.pl line 5.' >&2
+            echo 'build.pl had compilation errors.' >&2
+            exit 255";
+        // without perlcritic the syntax is checked by perl
+        let (_dir, settings) = fake_linters(&[("perl", script)]);
+        let result = Perl.execute(Path::new("build.pl"), &settings).unwrap();
+        assert_eq!(
+            result.message,
+            "line 3: syntax error, near \"= ;\"\n\
+             line 5: Global symbol \"$y\" requires explicit package name"
+        );
+        let findings = Perl.parse_findings(&result.message);
+        assert_eq!(findings.iter().map(|f| f.line).collect::<Vec<_>>(), [Some(3), Some(5)]);
+    }
+
+    #[test]
+    fn test_logged_output_with_stdin() {
+        let output = Command::new("cat")
+            .logged_output_with_stdin("echo hi\n", &RunSettings::default())
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "echo hi\n");
+    }
+
+    #[test]
+    fn test_split_output() {
+        let output =
+            "/tmp/a.rb:1:1: C: first\n  source\n/tmp/b.rb:2:1: W: second\n/tmp/a.rb:3:1: C: third";
+        let paths = [Path::new("/tmp/a.rb"), Path::new("/tmp/b.rb"), Path::new("/tmp/c.rb")];
+        assert_eq!(
+            split_output(output, &paths, |p| format!("{p}:")),
+            vec![
+                "/tmp/a.rb:1:1: C: first\n  source\n/tmp/a.rb:3:1: C: third\n",
+                "/tmp/b.rb:2:1: W: second\n",
+                "",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_timeout() {
+        let settings =
+            RunSettings { timeout: Some(Duration::from_millis(100)), ..Default::default() };
+        let result = Command::new("sleep").arg("5").logged_output(&settings);
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::TimedOut);
+    }
+
+    #[test]
+    fn test_wrapper_command() {
+        let wrapper = Wrapper {
+            executable: "rubocop",
+            command: vec!["bundle".to_string(), "exec".to_string(), "rubocop".to_string()],
+        };
+        let mut command = Command::new("rubocop");
+        command.arg("--stdin").arg("build.rb");
+        let wrapped = wrapper.command(&command).unwrap();
+        assert_eq!(wrapped.get_program(), "bundle");
+        assert_eq!(
+            wrapped.get_args().collect::<Vec<_>>(),
+            ["exec", "rubocop", "--stdin", "build.rb"]
+        );
+        assert!(wrapper.command(&Command::new("ruby")).is_none());
+    }
+
+    #[test]
+    fn test_config_source() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let project = temp_dir.path().join("project");
+        fs::create_dir_all(project.join("tasks")).unwrap();
+        fs::write(project.join("ruff.toml"), "line-length = 100\n").unwrap();
+        // not a ruff config without its section
+        fs::write(project.join("tasks/pyproject.toml"), "[project]\n").unwrap();
+
+        let mut command = Ruff::command(&RunSettings::default());
+        ConfigSource::Project(project.join("tasks")).apply(
+            &mut command,
+            &["pyproject.toml", "ruff.toml"],
+            "--isolated",
+        );
+        assert_eq!(command.get_current_dir(), Some(project.join("tasks").as_path()));
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(
+            args[args.len() - 2..],
+            ["--config".as_ref(), project.join("ruff.toml").as_os_str()]
+        );
+
+        let mut command = Ruff::command(&RunSettings::default());
+        ConfigSource::Isolated.apply(&mut command, &["ruff.toml"], "--isolated");
+        assert_eq!(command.get_args().last(), Some("--isolated".as_ref()));
+    }
+
+    #[test]
+    fn test_daemon_output() {
+        let runs = RefCell::new(vec![]);
+        let run = |server: bool| {
+            runs.borrow_mut().push(server);
+            let code = if server { "exit 2" } else { "exit 1" };
+            Command::new("sh").arg("-c").arg(code).output()
+        };
+        let settings = RunSettings {
+            config_source: Some(ConfigSource::Project(PathBuf::from("."))),
+            daemons: true,
+            ..RunSettings::default()
+        };
+        assert_eq!(settings.daemon_output("test", 1, run).unwrap().status.code(), Some(1));
+        // the failed server isn't tried again, also by the settings of other runs
+        settings.clone().daemon_output("test", 1, run).unwrap();
+        RunSettings::default().daemon_output("test", 1, run).unwrap();
+        assert_eq!(runs.into_inner(), [true, false, false, false]);
+    }
+
+    #[test]
+    fn test_environment() {
+        let environment = Environment {
+            allow: Some(vec!["HOME".to_string()]),
+            vars: BTreeMap::from([("RUFF_CACHE_DIR".to_string(), "/tmp/ruff".to_string())]),
+            working_dir: Some(PathBuf::from("/tmp")),
+        };
+        let envs = |command: &Command| -> Vec<String> {
+            command.get_envs().map(|(key, _)| key.to_string_lossy().into_owned()).collect()
+        };
+
+        let mut command = Command::new("ruff");
+        command.env("NO_COLOR", "1");
+        environment.apply(&mut command, false);
+        let mut expected = vec!["NO_COLOR", "RUFF_CACHE_DIR"];
+        expected.extend(["HOME", "PATH"].into_iter().filter(|k| env::var_os(k).is_some()));
+        expected.sort();
+        assert_eq!(envs(&command), expected);
+        assert_eq!(command.get_current_dir(), Some(Path::new("/tmp")));
+
+        let mut command = Command::new("ruff");
+        environment.apply(&mut command, true);
+        assert_eq!(envs(&command), ["RUFF_CACHE_DIR"]);
+        assert_eq!(command.get_current_dir(), None);
+    }
+
+    #[rstest]
+    #[case("echo 1;", true, "<?php\necho 1;")]
+    #[case("<?php\necho 1;", false, "<?php\necho 1;")]
+    fn test_php_opening_tag(#[case] source: &str, #[case] added: bool, #[case] content: &str) {
+        let script = Script { executor: "php".to_string(), source: source.to_string() };
+        let stages = pipeline(&script, &[], &Config::default());
+        assert_eq!(format!("{:?}", stages[0].handler), format!("Php {{ opening_tag: {added} }}"));
+        assert_eq!(stages[0].handler.content(&script).unwrap(), content);
+    }
+
+    #[test]
+    fn test_container_command() {
+        let container =
+            Container { image: "koalaman/shellcheck-alpine".to_string(), mount: "/tmp/x".into() };
+        let mut command = Command::new("shellcheck");
+        command.arg("--format=gcc").arg("/tmp/x/build.sh").env("NO_COLOR", "1");
+        let docker = container.command(&command);
+        assert_eq!(docker.get_program(), "docker");
+        assert_eq!(
+            docker.get_args().map(|a| a.to_string_lossy()).collect::<Vec<_>>().join(" "),
+            "run --rm --interactive --network=none --volume /tmp/x:/tmp/x --workdir /tmp/x \
+             --env NO_COLOR=1 --entrypoint shellcheck koalaman/shellcheck-alpine \
+             --format=gcc /tmp/x/build.sh"
+        );
+    }
+
+    #[rstest]
+    #[case("exit 0", true)]
+    #[case("exit 1", true)]
+    #[case("echo 'invalid config' >&2; exit 2", false)]
+    #[case("kill -9 $$", false)]
+    fn test_check_exit(#[case] script: &str, #[case] ok: bool) {
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(script)
+            .logged_output(&RunSettings::default())
+            .unwrap();
+        let result = check_exit("linter", &output, 1);
+        assert_eq!(result.is_ok(), ok);
+        if script.contains("invalid config") {
+            assert!(result.unwrap_err().to_string().ends_with(": invalid config"));
+        }
+    }
 }
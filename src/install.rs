@@ -0,0 +1,253 @@
+use anyhow::{anyhow, Context};
+use clap::ValueEnum;
+use log::debug;
+use sha2::{Digest, Sha256};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// The sha256 of the release download per tool, os and arch, updated together
+/// with the versions by `mask checksums`. The installed linters are put ahead
+/// of the PATH, so downloads without a pinned checksum are never installed.
+const CHECKSUMS: &[(Tool, &str, &str, &str)] = &[];
+
+/// Linters that provide static builds, so they can be installed without a
+/// package manager.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Tool {
+    Shellcheck,
+    Ruff,
+    Shfmt,
+}
+
+impl Tool {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tool::Shellcheck => "shellcheck",
+            Tool::Ruff => "ruff",
+            Tool::Shfmt => "shfmt",
+        }
+    }
+
+    /// The pinned version, updated together with masklint.
+    pub fn version(&self) -> &'static str {
+        match self {
+            Tool::Shellcheck => "0.10.0",
+            Tool::Ruff => "0.6.9",
+            Tool::Shfmt => "3.10.0",
+        }
+    }
+
+    fn sha256(&self, os: &str, arch: &str) -> Option<&'static str> {
+        CHECKSUMS
+            .iter()
+            .find(|(tool, o, a, _)| tool == self && *o == os && *a == arch)
+            .map(|(_, _, _, sha256)| *sha256)
+    }
+
+    /// The download of the release for the platform and the path of the
+    /// executable in it, if it's an archive.
+    fn release(&self, os: &str, arch: &str) -> Option<(String, Option<String>)> {
+        let version = self.version();
+        match self {
+            Tool::Shellcheck => {
+                let os = match os {
+                    "linux" => "linux",
+                    "macos" => "darwin",
+                    _ => return None,
+                };
+                let arch = matches!(arch, "x86_64" | "aarch64").then_some(arch)?;
+                Some((
+                    format!(
+                        "https://github.com/koalaman/shellcheck/releases/download/v{version}/shellcheck-v{version}.{os}.{arch}.tar.xz"
+                    ),
+                    Some(format!("shellcheck-v{version}/shellcheck")),
+                ))
+            }
+            Tool::Ruff => {
+                let target = match (os, arch) {
+                    ("linux", "x86_64" | "aarch64") => format!("{arch}-unknown-linux-musl"),
+                    ("macos", "x86_64" | "aarch64") => format!("{arch}-apple-darwin"),
+                    _ => return None,
+                };
+                Some((
+                    format!(
+                        "https://github.com/astral-sh/ruff/releases/download/{version}/ruff-{target}.tar.gz"
+                    ),
+                    Some(format!("ruff-{target}/ruff")),
+                ))
+            }
+            Tool::Shfmt => {
+                let os = match os {
+                    "linux" => "linux",
+                    "macos" => "darwin",
+                    _ => return None,
+                };
+                let arch = match arch {
+                    "x86_64" => "amd64",
+                    "aarch64" => "arm64",
+                    _ => return None,
+                };
+                Some((
+                    format!(
+                        "https://github.com/mvdan/sh/releases/download/v{version}/shfmt_v{version}_{os}_{arch}"
+                    ),
+                    None,
+                ))
+            }
+        }
+    }
+}
+
+/// Where the installed linters are kept, e.g. ~/.local/share/masklint/bin.
+pub fn install_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("masklint").join("bin"))
+}
+
+/// Makes the installed linters take precedence over the ones on the PATH.
+/// This has to be called before any threads are started.
+pub fn add_to_path() {
+    let Some(dir) = install_dir().filter(|d| d.is_dir()) else {
+        return;
+    };
+    let paths = env::var_os("PATH").unwrap_or_default();
+    let paths = std::iter::once(dir).chain(env::split_paths(&paths));
+    match env::join_paths(paths) {
+        Ok(paths) => env::set_var("PATH", paths),
+        Err(e) => debug!("failed to add the install dir to the PATH: {e}"),
+    }
+}
+
+/// Downloads the pinned release of the tool for the current platform into
+/// the dir and returns the path of the executable.
+pub fn install(tool: Tool, dir: &Path) -> anyhow::Result<PathBuf> {
+    let (os, arch) = (env::consts::OS, env::consts::ARCH);
+    let unsupported = || anyhow!("{} can't be installed on {os} {arch}", tool.name());
+    let (url, executable) = tool.release(os, arch).ok_or_else(unsupported)?;
+    let sha256 = tool.sha256(os, arch).ok_or_else(|| {
+        anyhow!("no checksum is pinned for {} {} on {os} {arch}", tool.name(), tool.version())
+    })?;
+    let tmp_dir = tempfile::tempdir()?;
+    let download = tmp_dir.path().join("download");
+    // curl and tar are available almost everywhere, so no http client or
+    // archive formats need to be bundled
+    run(Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location", "--output"])
+        .arg(&download)
+        .arg(&url))
+    .with_context(|| format!("failed to download {url}"))?;
+    verify(&download, sha256).with_context(|| format!("failed to verify {url}"))?;
+    let source = match executable {
+        Some(executable) => {
+            run(Command::new("tar").arg("-xf").arg(&download).arg("-C").arg(tmp_dir.path()))
+                .with_context(|| format!("failed to extract {url}"))?;
+            tmp_dir.path().join(executable)
+        }
+        None => download,
+    };
+
+    fs::create_dir_all(dir)?;
+    let target = dir.join(tool.name());
+    fs::copy(&source, &target)
+        .with_context(|| format!("failed to copy {} to {}", tool.name(), target.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(target)
+}
+
+/// Fails unless the file has the sha256, given in lowercase hex.
+fn verify(path: &Path, sha256: &str) -> anyhow::Result<()> {
+    let actual: String =
+        Sha256::digest(fs::read(path)?).iter().map(|b| format!("{b:02x}")).collect();
+    if actual != sha256 {
+        return Err(anyhow!("checksum mismatch, expected {sha256} but got {actual}"));
+    }
+    Ok(())
+}
+
+fn run(command: &mut Command) -> anyhow::Result<()> {
+    debug!("running {command:?}");
+    let output = command.output()?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "{} failed with {}: {}",
+            command.get_program().to_string_lossy(),
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(
+        Tool::Shellcheck,
+        "macos",
+        "aarch64",
+        "https://github.com/koalaman/shellcheck/releases/download/v0.10.0/shellcheck-v0.10.0.darwin.aarch64.tar.xz"
+    )]
+    #[case(
+        Tool::Ruff,
+        "linux",
+        "x86_64",
+        "https://github.com/astral-sh/ruff/releases/download/0.6.9/ruff-x86_64-unknown-linux-musl.tar.gz"
+    )]
+    #[case(
+        Tool::Shfmt,
+        "linux",
+        "aarch64",
+        "https://github.com/mvdan/sh/releases/download/v3.10.0/shfmt_v3.10.0_linux_arm64"
+    )]
+    fn test_release(#[case] tool: Tool, #[case] os: &str, #[case] arch: &str, #[case] url: &str) {
+        assert_eq!(tool.release(os, arch).unwrap().0, url);
+    }
+
+    #[test]
+    fn test_verify() {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let path = dir.path().join("download");
+        fs::write(&path, "abc").unwrap();
+        verify(&path, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad").unwrap();
+        let error = verify(&path, &"0".repeat(64)).unwrap_err();
+        assert!(error.to_string().starts_with("checksum mismatch"));
+    }
+
+    #[test]
+    fn test_checksums_pinned() {
+        for tool in Tool::value_variants() {
+            for (os, arch) in [("linux", "x86_64"), ("linux", "aarch64"), ("macos", "x86_64")]
+                .into_iter()
+                .chain([("macos", "aarch64"), ("windows", "x86_64")])
+            {
+                if tool.release(os, arch).is_some() {
+                    assert!(
+                        tool.sha256(os, arch).is_some(),
+                        "no checksum for {tool:?} on {os} {arch}"
+                    );
+                }
+            }
+        }
+        for (tool, os, arch, sha256) in CHECKSUMS {
+            assert!(tool.release(os, arch).is_some(), "{tool:?} isn't released for {os} {arch}");
+            assert!(
+                sha256.len() == 64 && sha256.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f'))
+            );
+        }
+    }
+
+    #[test]
+    fn test_release_unsupported() {
+        assert!(Tool::Ruff.release("windows", "x86_64").is_none());
+        assert!(Tool::Shfmt.release("linux", "riscv64").is_none());
+    }
+}
@@ -0,0 +1,1442 @@
+//! The core of masklint: extracting the scripts from a maskfile, running the
+//! linters on them and reporting the results. The CLI is a thin layer on top,
+//! so other tools can embed the linting with [`lint_maskfile`].
+
+use anyhow::anyhow;
+use indicatif::{ProgressBar, ProgressStyle};
+use log::debug;
+use mask_parser::maskfile::Script;
+use owo_colors::{Stream, Style};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+    fs,
+    io::{self, Read},
+    path::{Component, Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+pub mod baseline;
+pub mod cache;
+pub mod color;
+pub mod config;
+pub mod data;
+pub mod dump;
+pub mod fences;
+pub mod findings;
+pub mod handlers;
+pub mod install;
+pub mod report;
+pub mod structure;
+use baseline::{Baseline, Fingerprint};
+use cache::Cache;
+use color::paint;
+use config::Config;
+use dump::{write_file, DumpOptions};
+use fences::Fence;
+use findings::{Finding, Severity};
+use handlers::{
+    ConfigSource, Container, Environment, FailedDaemons, LintResult, LintResultType, RunSettings,
+    Runner, Stage, Wrapper,
+};
+use report::{CommandReport, CommandStatus, Timing};
+
+/// Settings that decide how the commands are linted and what fails them.
+#[derive(Debug, Clone)]
+pub struct LintOptions {
+    /// Minimum severity of findings that fail a command.
+    pub fail_level: Severity,
+    /// Treat warnings, e.g. for scripts without a linter, as failures.
+    pub strict: bool,
+    /// Skip linters that are not installed instead of returning an error.
+    pub allow_missing_linters: bool,
+    pub config: Config,
+    /// Findings that are not reported.
+    pub baseline: Baseline,
+    pub cache: Option<Cache>,
+    /// Kills linters that take longer, unless the config sets a timeout for it.
+    pub timeout: Option<Duration>,
+    pub runner: Runner,
+    /// Only lints the commands whose scripts changed since this git revision,
+    /// which requires the maskfile to be read from a path.
+    pub changed_since: Option<String>,
+    /// Lints with the linters' defaults instead of the project's config.
+    pub isolated: bool,
+    /// Runs the linters that have a server through it, which keeps running
+    /// in the background to speed up later runs.
+    pub daemons: bool,
+    /// Checks the syntax of the yaml, json and toml blocks in the maskfile.
+    pub data_blocks: bool,
+    /// Records the errors of single commands and maskfiles, e.g. a linter
+    /// that is not installed or crashed, and continues with the others.
+    pub keep_going: bool,
+    /// The linters whose server failed, shared with the clones of the options.
+    pub failed_daemons: FailedDaemons,
+}
+
+impl Default for LintOptions {
+    fn default() -> Self {
+        LintOptions {
+            fail_level: Severity::Style,
+            strict: false,
+            allow_missing_linters: false,
+            config: Config::default(),
+            baseline: Baseline::default(),
+            cache: None,
+            timeout: None,
+            runner: Runner::Native,
+            changed_since: None,
+            isolated: false,
+            daemons: true,
+            data_blocks: false,
+            keep_going: false,
+            failed_daemons: FailedDaemons::default(),
+        }
+    }
+}
+
+/// Lints all commands of the maskfile and returns a report per command that
+/// has a script. Nothing is printed, so the reports can be rendered with
+/// [`report::render`] or processed further.
+///
+/// ```no_run
+/// use masklint::{lint_maskfile, report::CommandStatus, LintOptions};
+///
+/// let reports = lint_maskfile("maskfile.md".as_ref(), &LintOptions::default())?;
+/// let failed = reports.iter().filter(|r| r.status == CommandStatus::Failed).count();
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub fn lint_maskfile(
+    maskfile_path: &Path,
+    options: &LintOptions,
+) -> anyhow::Result<Vec<CommandReport>> {
+    let tmp_dir = tempfile::tempdir()?;
+    let context = ProcessCommandContext {
+        out_dir: tmp_dir.path().to_path_buf(),
+        dump: None,
+        print_results: false,
+        no_warnings: false,
+        options: options.clone(),
+        maskfile_label: None,
+        project_dir: None,
+        progress: false,
+    };
+    Ok(process_maskfile(maskfile_path, &context)?.reports)
+}
+
+/// Everything the CLI needs on top of the [`LintOptions`] to process maskfiles.
+#[derive(Clone)]
+pub struct ProcessCommandContext {
+    pub out_dir: PathBuf,
+    /// Writes the scripts to the out dir instead of linting them.
+    pub dump: Option<DumpOptions>,
+    /// Prints the linter output of every command right away.
+    pub print_results: bool,
+    pub no_warnings: bool,
+    pub options: LintOptions,
+    /// Shown in the command headers to tell apart commands from different maskfiles.
+    pub maskfile_label: Option<String>,
+    /// Where the linters look for the project's config, usually the maskfile's dir.
+    pub project_dir: Option<PathBuf>,
+    /// Shows a progress bar with the running linter on stderr.
+    pub progress: bool,
+}
+
+/// Returned when the executable of a linter isn't installed.
+#[derive(Debug)]
+pub struct MissingLinter(pub String);
+
+impl Display for MissingLinter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "executable for {} not found in $PATH", self.0)
+    }
+}
+
+impl std::error::Error for MissingLinter {}
+
+/// Aggregated outcome of processing commands.
+#[derive(Default)]
+pub struct Summary {
+    /// Number of commands with lint failures.
+    pub findings: u32,
+    /// Number of single findings that fail the run.
+    pub failed_findings: u32,
+    /// Number of findings that are not reported because of the baseline.
+    pub baselined: u32,
+    /// Linters that were skipped because they are not installed.
+    pub missing_linters: BTreeSet<String>,
+    /// Linters that are older than their minimum version, with the versions.
+    pub outdated_linters: BTreeSet<String>,
+    /// All findings, including the ones in the baseline, with how often they
+    /// occurred.
+    pub fingerprints: BTreeMap<Fingerprint, u32>,
+    pub reports: Vec<CommandReport>,
+    pub timings: Vec<Timing>,
+    /// Errors that didn't abort the run because of `keep_going`.
+    pub errors: Vec<anyhow::Error>,
+}
+
+impl Summary {
+    pub fn add(&mut self, other: Summary) {
+        self.findings += other.findings;
+        self.failed_findings += other.failed_findings;
+        self.baselined += other.baselined;
+        self.missing_linters.extend(other.missing_linters);
+        self.outdated_linters.extend(other.outdated_linters);
+        for (fingerprint, count) in other.fingerprints {
+            *self.fingerprints.entry(fingerprint).or_default() += count;
+        }
+        self.reports.extend(other.reports);
+        self.timings.extend(other.timings);
+        self.errors.extend(other.errors);
+    }
+
+    pub fn print_outdated_linters(&self) {
+        for outdated in &self.outdated_linters {
+            let note = format!("Outdated linter: {outdated}");
+            eprintln!("{}", paint(note, Stream::Stderr, Style::new().yellow()));
+        }
+    }
+
+    pub fn print_errors(&self) {
+        if self.errors.is_empty() {
+            return;
+        }
+        let plural = if self.errors.len() == 1 { "" } else { "s" };
+        let header = format!("{} error{plural} while linting:", self.errors.len());
+        eprintln!("{}", paint(header, Stream::Stderr, Style::new().bold().red()));
+        for error in &self.errors {
+            eprintln!("  {error:#}");
+        }
+    }
+
+    pub fn print_missing_linters(&self) {
+        if self.missing_linters.is_empty() {
+            return;
+        }
+        let linters = self.missing_linters.iter().cloned().collect::<Vec<String>>();
+        let note = format!("Skipped linters that are not installed: {}", linters.join(", "));
+        eprintln!("{}", paint(note, Stream::Stderr, Style::new().yellow()));
+    }
+}
+
+impl ProcessCommandContext {
+    /// The linter's timeout from the config takes precedence over `--timeout`.
+    fn linter_timeout(&self, handler: &dyn handlers::LanguageHandler) -> Option<Duration> {
+        self.options.config.linter_timeout(&handler.to_string()).or(self.options.timeout)
+    }
+
+    /// Linters without an image are run on the host even with the docker runner.
+    fn container(&self, handler: &dyn handlers::LanguageHandler) -> Option<Container> {
+        if self.options.runner != Runner::Docker {
+            return None;
+        }
+        let linter = handler.to_string();
+        let image = match self.options.config.linter_image(&linter) {
+            Some(image) => image.to_string(),
+            None => Container::default_image(&linter)?.to_string(),
+        };
+        Some(Container { image, mount: self.out_dir.clone() })
+    }
+
+    /// The configured command replaces the first executable, which is the
+    /// linter itself while the others are only fallbacks or additions.
+    fn wrapper(&self, handler: &dyn handlers::LanguageHandler) -> Option<Wrapper> {
+        let command = self.options.config.linter_command(&handler.to_string())?;
+        let executable = *handler.executables().first()?;
+        Some(Wrapper { executable, command: command.to_vec() })
+    }
+
+    fn environment(&self, handler: &dyn handlers::LanguageHandler) -> Option<Environment> {
+        let config = &self.options.config;
+        let linter = handler.to_string();
+        let environment = Environment {
+            allow: config.env.allow.clone(),
+            vars: config.linter_env(&linter),
+            working_dir: config.linter_working_dir(&linter).map(Path::to_path_buf),
+        };
+        let empty = environment.allow.is_none()
+            && environment.vars.is_empty()
+            && environment.working_dir.is_none();
+        (!empty).then_some(environment)
+    }
+
+    fn config_source(&self) -> Option<ConfigSource> {
+        if self.options.isolated {
+            return Some(ConfigSource::Isolated);
+        }
+        // only the out dir is mounted into the containers
+        if self.options.runner == Runner::Docker {
+            return None;
+        }
+        self.project_dir.clone().map(ConfigSource::Project)
+    }
+
+    /// What the linter of the handler is run with besides the script.
+    fn run_settings(&self, handler: &dyn handlers::LanguageHandler) -> RunSettings {
+        RunSettings {
+            timeout: self.linter_timeout(handler),
+            container: self.container(handler),
+            wrapper: self.wrapper(handler),
+            config_source: self.config_source(),
+            daemons: self.options.daemons,
+            failed_daemons: self.options.failed_daemons.clone(),
+            environment: self.environment(handler),
+        }
+    }
+
+    /// A bar for the given number of linter runs, hidden unless enabled.
+    fn progress_bar(&self, length: u64) -> ProgressBar {
+        if !self.progress || length == 0 {
+            return ProgressBar::hidden();
+        }
+        let style = ProgressStyle::with_template("{spinner} [{bar:30}] {pos}/{len} {wide_msg}")
+            .expect("the template is valid")
+            .progress_chars("=> ");
+        let bar = ProgressBar::new(length).with_style(style);
+        bar.enable_steady_tick(Duration::from_millis(100));
+        bar
+    }
+
+    /// Prefixes the command name with the maskfile if there are multiple ones.
+    fn label(&self, command_name: &str) -> String {
+        match &self.maskfile_label {
+            Some(label) => format!("{label}: {command_name}"),
+            None => command_name.to_string(),
+        }
+    }
+
+    /// Derives the context for one out of multiple maskfiles. Its scripts are
+    /// written to a separate subdirectory to avoid clashing file names.
+    pub fn for_maskfile(&self, maskfile_path: &Path) -> anyhow::Result<Self> {
+        let dir_name_path =
+            if is_stdin(maskfile_path) { Path::new("stdin") } else { maskfile_path };
+        let dir_name = dir_name_path
+            .with_extension("")
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(c) => Some(c.to_string_lossy().into_owned()),
+                _ => None,
+            })
+            .collect::<Vec<String>>()
+            .join("_");
+        let out_dir = self.out_dir.join(dir_name);
+        fs::create_dir_all(&out_dir)?;
+        Ok(ProcessCommandContext {
+            out_dir,
+            maskfile_label: Some(display_maskfile(maskfile_path)),
+            ..self.clone()
+        })
+    }
+}
+
+pub fn process_maskfiles(
+    maskfiles: &[PathBuf],
+    context: &ProcessCommandContext,
+) -> anyhow::Result<Summary> {
+    if let [maskfile_path] = maskfiles {
+        return process_maskfile(maskfile_path, context);
+    }
+
+    let mut summary = Summary::default();
+    for maskfile_path in maskfiles {
+        if context.print_results {
+            let section = format!("==> {}", display_maskfile(maskfile_path));
+            println!("{}", paint(section, Stream::Stdout, Style::new().bold()));
+        }
+        match process_maskfile(maskfile_path, &context.for_maskfile(maskfile_path)?) {
+            Ok(maskfile_summary) => summary.add(maskfile_summary),
+            Err(e) if context.options.keep_going => {
+                summary.errors.push(e.context(display_maskfile(maskfile_path)));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(summary)
+}
+
+/// How a maskfile read from stdin is labeled in the reports.
+pub const STDIN_LABEL: &str = "<stdin>";
+
+pub fn is_stdin(maskfile_path: &Path) -> bool {
+    maskfile_path == Path::new("-")
+}
+
+pub fn display_maskfile(maskfile_path: &Path) -> String {
+    if is_stdin(maskfile_path) {
+        return STDIN_LABEL.to_string();
+    }
+    maskfile_path.display().to_string()
+}
+
+fn read_maskfile(maskfile_path: &Path) -> io::Result<String> {
+    if is_stdin(maskfile_path) {
+        let mut content = String::new();
+        io::stdin().read_to_string(&mut content)?;
+        return Ok(content);
+    }
+    fs::read_to_string(maskfile_path)
+}
+
+pub fn process_maskfile(
+    maskfile_path: &Path,
+    context: &ProcessCommandContext,
+) -> anyhow::Result<Summary> {
+    if is_stdin(maskfile_path) && context.options.changed_since.is_some() {
+        return Err(anyhow!("changes can't be detected when reading the maskfile from stdin"));
+    }
+    let content = read_maskfile(maskfile_path)?;
+    let previous = match &context.options.changed_since {
+        Some(rev) => Some(read_maskfile_at(rev, maskfile_path)?),
+        None => None,
+    };
+    let project_dir = match maskfile_path.parent() {
+        _ if is_stdin(maskfile_path) => context.project_dir.clone(),
+        Some(dir) if !dir.as_os_str().is_empty() => Some(dir.to_path_buf()),
+        _ => Some(PathBuf::from(".")),
+    };
+    let context = &ProcessCommandContext { project_dir, ..context.clone() };
+    process(content, previous, &display_maskfile(maskfile_path), context)
+}
+
+/// Reads the maskfile as it was in the git revision, which is empty if it
+/// didn't exist back then.
+fn read_maskfile_at(rev: &str, maskfile_path: &Path) -> anyhow::Result<String> {
+    let dir =
+        maskfile_path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let file_name = maskfile_path
+        .file_name()
+        .ok_or_else(|| anyhow!("invalid maskfile path {}", maskfile_path.display()))?;
+    let git =
+        |args: &[&str]| std::process::Command::new("git").arg("-C").arg(dir).args(args).output();
+    let verify = git(&["rev-parse", "--verify", "--quiet", &format!("{rev}^{{commit}}")])?;
+    if !verify.status.success() {
+        return Err(anyhow!("unknown git revision {rev}"));
+    }
+    // "./" makes the path relative to the dir instead of the repository root
+    let output = git(&["show", &format!("{rev}:./{}", file_name.to_string_lossy())])?;
+    if !output.status.success() {
+        debug!("{} didn't exist in {rev}", maskfile_path.display());
+        return Ok(String::new());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Processes a maskfile that was already read, e.g. the unsaved content from
+/// an editor. The name is only used in the reports.
+pub fn process_maskfile_content(
+    content: String,
+    maskfile_name: &str,
+    context: &ProcessCommandContext,
+) -> anyhow::Result<Summary> {
+    process(content, None, maskfile_name, context)
+}
+
+/// Lints the scripts of the maskfile, only the ones that differ from the
+/// previous content if there is one.
+fn process(
+    content: String,
+    previous: Option<String>,
+    maskfile_name: &str,
+    context: &ProcessCommandContext,
+) -> anyhow::Result<Summary> {
+    let mut scripts = parse_scripts(content.clone(), context)?;
+    if let Some(previous) = previous {
+        // changed arguments change the content as well since they are
+        // declared for some linters
+        let previous: BTreeSet<(String, String)> = parse_scripts(previous, context)?
+            .into_iter()
+            .map(|s| (s.label(), s.lint_content()))
+            .collect();
+        let total = scripts.len();
+        scripts.retain(|s| !previous.contains(&(s.label(), s.lint_content())));
+        let unchanged = total - scripts.len();
+        if context.print_results && unchanged > 0 {
+            let note = format!("Skipped {unchanged} unchanged command(s)");
+            println!("{}", paint(note, Stream::Stdout, Style::new().dimmed()));
+        }
+    }
+
+    let mut summary = Summary::default();
+    if let Some(options) = &context.dump {
+        dump::dump_scripts(&scripts, maskfile_name, &context.out_dir, options)?;
+        return Ok(summary);
+    }
+    check_linter_versions(context, &scripts, &mut summary)?;
+    let results = lint_scripts(context, &scripts, &mut summary)?;
+    for (script, results) in scripts.iter().zip(results) {
+        report_script(context, script, results, &mut summary)?;
+    }
+    if context.options.config.linter_enabled(structure::LINTER, true) {
+        let findings = structure::check(&content, &context.options.config);
+        report_maskfile_findings(context, structure::LINTER, findings, &mut summary);
+    }
+    if context.options.data_blocks {
+        report_maskfile_findings(context, data::LINTER, data::check(&content), &mut summary);
+    }
+    for report in &mut summary.reports {
+        report.maskfile = maskfile_name.to_string();
+    }
+    Ok(summary)
+}
+
+fn parse_scripts(
+    content: String,
+    context: &ProcessCommandContext,
+) -> anyhow::Result<Vec<ExtractedScript>> {
+    let fences = fences::script_fences(&content);
+    let windows_fences = fences::windows_fences(&content);
+    // mask-parser drops the windows scripts on other platforms, but on windows
+    // the last block is the script in any language, so they'd be linted twice
+    let windows_scripts = if cfg!(windows) { vec![] } else { structure::windows_scripts(&content) };
+    let windows_commands: BTreeSet<&str> =
+        windows_scripts.iter().map(|w| w.command.as_str()).collect();
+    let maskfile = mask_parser::parse(content);
+    let mut scripts = vec![];
+    for command in maskfile.commands {
+        extract_scripts(context, command, None, &windows_commands, &mut scripts)?;
+    }
+    attach_fences(&mut scripts, fences);
+
+    let main_scripts = scripts.len();
+    for windows in &windows_scripts {
+        scripts.push(extract_script(
+            context,
+            &windows.command,
+            &windows.script,
+            &windows.variables,
+            Some("windows"),
+        )?);
+    }
+    attach_fences(&mut scripts[main_scripts..], windows_fences);
+    make_file_names_unique(&mut scripts);
+    Ok(scripts)
+}
+
+/// Gives the scripts the positions of their code blocks. Commands that are
+/// defined more than once can't be told apart, so they get none.
+fn attach_fences(scripts: &mut [ExtractedScript], fences: Vec<(String, Fence)>) {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for script in scripts.iter() {
+        *counts.entry(script.command.clone()).or_default() += 1;
+    }
+    let mut fences_by_command: BTreeMap<String, Vec<Fence>> = BTreeMap::new();
+    for (command, fence) in fences {
+        fences_by_command.entry(command).or_default().push(fence);
+    }
+    for script in scripts {
+        script.fence = match fences_by_command.get(&script.command).map(Vec::as_slice) {
+            Some([fence]) if counts[&script.command] == 1 => Some(*fence),
+            _ => None,
+        };
+    }
+}
+
+/// Different commands can end up with the same file, e.g. `db migrate` and
+/// `db_migrate`, so the colliding ones get a short hash of the command name
+/// before the extension. It doesn't depend on the other commands, so the name
+/// stays the same as long as the collision does.
+fn make_file_names_unique(scripts: &mut [ExtractedScript]) {
+    // case insensitive file systems can't tell apart the cases either
+    let key = |s: &ExtractedScript| s.file_path.to_string_lossy().to_lowercase();
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for script in scripts.iter() {
+        *counts.entry(key(script)).or_default() += 1;
+    }
+    // the same command can be defined twice, which only the position tells apart
+    let mut seen: BTreeMap<String, usize> = BTreeMap::new();
+    for script in scripts.iter_mut().filter(|s| counts[&key(s)] > 1) {
+        let occurrence = seen.entry(script.label()).or_default();
+        let mut hasher = Sha256::new();
+        hasher.update(script.label());
+        hasher.update(occurrence.to_string());
+        *occurrence += 1;
+        let hash: String = hasher.finalize()[..4].iter().map(|b| format!("{b:02x}")).collect();
+
+        // the file name is the command name with underscores and the extension
+        let extension = script.file_name[script.command.len()..].to_string();
+        let with_hash = |name: &str| match name.strip_suffix(&extension) {
+            Some(stem) => format!("{stem}-{hash}{extension}"),
+            None => format!("{name}-{hash}"),
+        };
+        script.file_name = with_hash(&script.file_name);
+        let path_name = script.file_path.file_name().unwrap_or_default().to_string_lossy();
+        script.file_path = script.file_path.with_file_name(with_hash(&path_name));
+        debug!("using {} for {} to avoid a collision", script.file_name, script.label());
+    }
+}
+
+/// A script extracted from a command together with the linters to run on it.
+pub(crate) struct ExtractedScript {
+    pub(crate) command: String,
+    /// The platform of the script if the command has one for windows and one
+    /// for everything else.
+    pub(crate) variant: Option<&'static str>,
+    pub(crate) executor: String,
+    /// Where the script is in the maskfile.
+    pub(crate) fence: Option<Fence>,
+    stages: Vec<Stage>,
+    file_name: String,
+    pub(crate) file_path: PathBuf,
+    pub(crate) content: String,
+    /// Added in front of the content for linting, see [`handlers::LanguageHandler::preamble`].
+    preamble: String,
+}
+
+impl ExtractedScript {
+    fn enabled_stages(&self) -> impl Iterator<Item = &Stage> {
+        self.stages.iter().filter(|s| s.enabled)
+    }
+
+    /// What the linters get, the content with the preamble of the main linter.
+    fn lint_content(&self) -> String {
+        format!("{}{}", self.preamble, self.content)
+    }
+
+    /// The command name, followed by the platform of the variant.
+    pub(crate) fn label(&self) -> String {
+        match self.variant {
+            Some(variant) => format!("{} ({variant})", self.command),
+            None => self.command.clone(),
+        }
+    }
+}
+
+// Function to extract the scripts of a command and its subcommands
+fn extract_scripts(
+    context: &ProcessCommandContext,
+    command: mask_parser::maskfile::Command,
+    parent_name: Option<&str>,
+    windows_commands: &BTreeSet<&str>,
+    scripts: &mut Vec<ExtractedScript>,
+) -> anyhow::Result<()> {
+    // Build full command name including parent
+    let full_command_name = match parent_name {
+        Some(parent) => format!("{} {}", parent, command.name),
+        None => command.name,
+    };
+
+    if let Some(script) = command.script {
+        // mask sets the arguments and options as environment variables
+        let variables: Vec<String> = command
+            .required_args
+            .iter()
+            .map(|a| a.name.clone())
+            .chain(command.optional_args.iter().map(|a| a.name.clone()))
+            .chain(command.named_flags.iter().map(|f| f.name.clone()))
+            .collect();
+        let variant = windows_commands.contains(full_command_name.as_str()).then_some("linux");
+        scripts.push(extract_script(context, &full_command_name, &script, &variables, variant)?);
+    }
+
+    // Process subcommands recursively
+    for subcmd in command.subcommands {
+        extract_scripts(context, subcmd, Some(&full_command_name), windows_commands, scripts)?;
+    }
+    Ok(())
+}
+
+/// Prepares the script for the linters, variants get the platform in their
+/// file names, e.g. build.windows.ps1.
+fn extract_script(
+    context: &ProcessCommandContext,
+    full_command_name: &str,
+    script: &Script,
+    variables: &[String],
+    variant: Option<&'static str>,
+) -> anyhow::Result<ExtractedScript> {
+    let stages = handlers::pipeline(script, variables, &context.options.config);
+    // the pipeline is never empty and the first linter is the main one
+    let language_handler = &stages[0].handler;
+
+    let extension = match variant {
+        Some(variant) => format!(".{variant}{}", language_handler.file_extension()),
+        None => language_handler.file_extension().to_string(),
+    };
+    let file_name = format!("{}{extension}", full_command_name.replace(" ", "_"));
+    let layout = context.dump.as_ref().map(|d| d.layout).unwrap_or_default();
+    let file_path = dump::script_path(&context.out_dir, full_command_name, &extension, layout);
+    let content = language_handler.content(script)?;
+    let preamble = language_handler.preamble();
+    Ok(ExtractedScript {
+        command: full_command_name.to_string(),
+        variant,
+        executor: script.executor.clone(),
+        fence: None,
+        stages,
+        file_name,
+        file_path,
+        content,
+        preamble,
+    })
+}
+
+/// Compares the linters of the scripts with the minimum versions from the
+/// config before any of them is run. Outdated linters fail the run unless
+/// they are allowed, linters that aren't installed are reported when linting.
+fn check_linter_versions(
+    context: &ProcessCommandContext,
+    scripts: &[ExtractedScript],
+    summary: &mut Summary,
+) -> anyhow::Result<()> {
+    let mut checked = BTreeSet::new();
+    for stage in scripts.iter().flat_map(|s| s.enabled_stages()) {
+        let handler = stage.handler.as_ref();
+        let linter = handler.to_string();
+        let Some(min_version) = context.options.config.linter_min_version(&linter) else {
+            continue;
+        };
+        if !checked.insert(linter.clone()) {
+            continue;
+        }
+        let required = handlers::parse_version(min_version)
+            .ok_or_else(|| anyhow!("invalid min_version {min_version} of {linter}"))?;
+        let Some(executable) = handler.executables().first().copied() else {
+            continue;
+        };
+        let Some(output) = context.run_settings(handler).version_output(executable) else {
+            continue;
+        };
+        let Some(version) = handlers::parse_version(&output) else {
+            debug!("no version found in the output of {executable} --version: {output}");
+            continue;
+        };
+        if version >= required {
+            continue;
+        }
+        let found = output.lines().find(|l| l.chars().any(|c| c.is_ascii_digit()));
+        let outdated = format!(
+            "{linter} is older than the required {min_version}: {}",
+            found.unwrap_or_default().trim()
+        );
+        if context.options.config.allow_outdated_linters {
+            summary.outdated_linters.insert(outdated);
+        } else if context.options.keep_going {
+            summary.errors.push(anyhow!(outdated));
+        } else {
+            return Err(anyhow!(outdated));
+        }
+    }
+    Ok(())
+}
+
+/// Runs the enabled linters on all scripts and returns the results per script
+/// in the order of its stages. Linters that support it are run once for all
+/// scripts instead of once per script, since starting them often takes longer
+/// than the actual linting.
+fn lint_scripts(
+    context: &ProcessCommandContext,
+    scripts: &[ExtractedScript],
+    summary: &mut Summary,
+) -> anyhow::Result<Vec<Vec<io::Result<LintResult>>>> {
+    let stages: Vec<Vec<&Stage>> = scripts.iter().map(|s| s.enabled_stages().collect()).collect();
+    let mut results: Vec<Vec<Option<io::Result<LintResult>>>> =
+        stages.iter().map(|s| s.iter().map(|_| None).collect()).collect();
+
+    let mut singles = vec![];
+    let mut batches: BTreeMap<String, Vec<(usize, usize)>> = BTreeMap::new();
+    for (i, script) in scripts.iter().enumerate() {
+        for (j, stage) in stages[i].iter().enumerate() {
+            // the key depends on the linter's settings, e.g. the wrapper's version
+            let handler = stage.handler.as_ref();
+            let cached = context.options.cache.as_ref().and_then(|c| {
+                let settings = context.run_settings(handler);
+                c.get(handler, &settings, &script.file_name, &script.lint_content())
+            });
+            if let Some(result) = cached {
+                results[i][j] = Some(Ok(result));
+                continue;
+            }
+            match stage.handler.batch_key() {
+                Some(key) => batches.entry(key).or_default().push((i, j)),
+                None => singles.push((i, j)),
+            }
+        }
+    }
+    batches.retain(|_, members| match members.as_slice() {
+        [single] => {
+            singles.push(*single);
+            false
+        }
+        _ => true,
+    });
+
+    // linters that support it get the script over stdin, so it's only
+    // written if one of the linters needs the file
+    let mut needs_file = vec![false; scripts.len()];
+    for &(i, j) in &singles {
+        needs_file[i] |= !stages[i][j].handler.supports_stdin();
+    }
+    for &(i, _) in batches.values().flatten() {
+        needs_file[i] = true;
+    }
+    for (script, _) in scripts.iter().zip(needs_file).filter(|(_, needs)| *needs) {
+        write_file(&script.file_path, &script.lint_content(), false)?;
+    }
+
+    let progress = context.progress_bar((singles.len() + batches.len()) as u64);
+    let mut record = |i: usize, j: usize, result: io::Result<LintResult>, duration| {
+        let (script, handler) = (&scripts[i], &stages[i][j].handler);
+        summary.timings.push(Timing {
+            command: context.label(&script.label()),
+            linter: handler.to_string(),
+            duration,
+        });
+        if let (Some(cache), Ok(result)) = (&context.options.cache, &result) {
+            let settings = context.run_settings(handler.as_ref());
+            cache.put(
+                handler.as_ref(),
+                &settings,
+                &script.file_name,
+                &script.lint_content(),
+                result,
+            );
+        }
+        results[i][j] = Some(result);
+    };
+    for (i, j) in singles {
+        let (script, handler) = (&scripts[i], &stages[i][j].handler);
+        progress.set_message(format!("{}: {handler}", context.label(&script.label())));
+        let start = Instant::now();
+        let settings = context.run_settings(handler.as_ref());
+        let result = if handler.supports_stdin() {
+            handler.execute_stdin(&script.lint_content(), &script.file_name, &settings)
+        } else {
+            handler.execute(&script.file_path, &settings)
+        };
+        record(i, j, result, start.elapsed());
+        progress.inc(1);
+    }
+    for members in batches.into_values() {
+        let (first, _) = members[0];
+        let handler = &stages[first][members[0].1].handler;
+        let paths: Vec<&Path> =
+            members.iter().map(|&(i, _)| scripts[i].file_path.as_path()).collect();
+        let label = context.label(&format!("{} commands", members.len()));
+        progress.set_message(format!("{label}: {handler}"));
+        let start = Instant::now();
+        let settings = context.run_settings(handler.as_ref());
+        let batch = handler.execute_batch(&paths, &settings);
+        // the time can't be measured per script, so it's split evenly
+        let duration = start.elapsed() / members.len() as u32;
+        match batch {
+            Ok(batch) => {
+                for (&(i, j), result) in members.iter().zip(batch) {
+                    record(i, j, Ok(result), duration);
+                }
+            }
+            Err(e) => {
+                for &(i, j) in &members {
+                    record(i, j, Err(io::Error::new(e.kind(), e.to_string())), duration);
+                }
+            }
+        }
+        progress.inc(1);
+    }
+    // the results are printed afterwards, which would mix with the bar
+    progress.finish_and_clear();
+
+    Ok(results
+        .into_iter()
+        .map(|r| r.into_iter().map(|r| r.expect("every stage is run")).collect())
+        .collect())
+}
+
+fn report_script(
+    context: &ProcessCommandContext,
+    script: &ExtractedScript,
+    executions: Vec<io::Result<LintResult>>,
+    summary: &mut Summary,
+) -> anyhow::Result<()> {
+    let full_command_name = &script.label();
+    let mut results = vec![];
+    for (stage, execution) in script.enabled_stages().zip(executions) {
+        let handler = &stage.handler;
+        let result = match execution {
+            Ok(result) => result.parse(handler.as_ref()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                if context.options.allow_missing_linters {
+                    summary.missing_linters.insert(handler.to_string());
+                    LintResult::warning(format!("{handler} is not installed, skipping it"))
+                } else {
+                    let error = anyhow::Error::new(MissingLinter(handler.to_string()));
+                    keep_going(context, full_command_name, error, summary)?
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => {
+                LintResult::error(format!("{handler} {e}"))
+            }
+            Err(e) => {
+                let error = anyhow!(e).context(format!("{handler} failed"));
+                keep_going(context, full_command_name, error, summary)?
+            }
+        };
+        results.push((handler.to_string(), result));
+    }
+    let linters = results.iter().map(|(linter, _)| linter.clone()).collect();
+    add_report(
+        context,
+        full_command_name,
+        linters,
+        LintResult::merge(results),
+        &script.file_path,
+        script.fence,
+        summary,
+    );
+    Ok(())
+}
+
+/// Records the error of a command with `keep_going` and fails the command with
+/// it instead, otherwise the error is returned.
+fn keep_going(
+    context: &ProcessCommandContext,
+    full_command_name: &str,
+    error: anyhow::Error,
+    summary: &mut Summary,
+) -> anyhow::Result<LintResult> {
+    if !context.options.keep_going {
+        return Err(error);
+    }
+    let result = LintResult::error(format!("{error:#}"));
+    summary.errors.push(error.context(context.label(full_command_name)));
+    Ok(result)
+}
+
+/// Reports findings with lines in the maskfile, like the ones of its structure,
+/// as if they were a command.
+fn report_maskfile_findings(
+    context: &ProcessCommandContext,
+    linter: &str,
+    findings: Vec<Finding>,
+    summary: &mut Summary,
+) {
+    if findings.is_empty() {
+        return;
+    }
+    let message = findings
+        .iter()
+        .map(|f| {
+            let mut location = format!("line {}", f.line.unwrap_or_default());
+            if let Some(column) = f.column {
+                location.push_str(&format!(":{column}"));
+            }
+            let rule = f.rule.as_deref().map(|r| format!("{r} ")).unwrap_or_default();
+            format!("{location}: {rule}{}: {}", f.severity, f.message)
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    let lint_result = LintResult { findings, ..LintResult::findings(message) };
+    let linters = vec![linter.to_string()];
+    add_report(context, linter, linters, lint_result, Path::new(""), None, summary);
+}
+
+/// Whether the findings of the report have lines in the maskfile instead of a
+/// script, like the ones of the structure and the data blocks.
+pub fn has_maskfile_lines(report: &CommandReport) -> bool {
+    report.linters == [structure::LINTER] || report.linters == [data::LINTER]
+}
+
+/// Applies the baseline and the fail level to the result of a command and
+/// adds its report to the summary.
+fn add_report(
+    context: &ProcessCommandContext,
+    full_command_name: &str,
+    linters: Vec<String>,
+    mut lint_result: LintResult,
+    script_path: &Path,
+    fence: Option<Fence>,
+    summary: &mut Summary,
+) {
+    let total = lint_result.findings.len();
+    let maskfile = context.maskfile_label.as_deref();
+    lint_result.findings.retain(|f| {
+        let fingerprint = Fingerprint::new(maskfile, full_command_name, f, script_path);
+        let baselined = context.options.baseline.count(&fingerprint);
+        let count = summary.fingerprints.entry(fingerprint).or_default();
+        *count += 1;
+        *count > baselined
+    });
+    let baselined = total - lint_result.findings.len();
+    summary.baselined += baselined as u32;
+    let all_baselined = total > 0 && baselined == total;
+    let status = match lint_result.result_type {
+        _ if lint_result.message.is_empty() || all_baselined => CommandStatus::Passed,
+        LintResultType::Findings => {
+            let failed = lint_result
+                .findings
+                .iter()
+                .filter(|f| f.severity >= context.options.fail_level)
+                .count() as u32;
+            summary.failed_findings += failed;
+            if failed > 0 {
+                CommandStatus::Failed
+            } else {
+                CommandStatus::Passed
+            }
+        }
+        LintResultType::Warning if context.options.strict => {
+            summary.failed_findings += 1;
+            CommandStatus::Failed
+        }
+        LintResultType::Warning => CommandStatus::Warning,
+    };
+    if status == CommandStatus::Failed {
+        summary.findings += 1;
+    }
+
+    let report = CommandReport {
+        maskfile: context.maskfile_label.clone().unwrap_or_default(),
+        command: full_command_name.to_string(),
+        status,
+        linters,
+        findings: lint_result.findings,
+        output: if all_baselined { String::new() } else { lint_result.message },
+        fence,
+    };
+    if context.print_results && report.is_shown(context.no_warnings) {
+        print!("{}", report::human_section(&report, context.maskfile_label.is_some(), true));
+        if baselined > 0 {
+            let note = format!("{baselined} of these are in the baseline");
+            println!("{}", paint(note, Stream::Stdout, Style::new().dimmed()));
+        }
+    }
+    summary.reports.push(report);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("maskfile.md", 3)]
+    #[case("nested_maskfile.md", 2)]
+    fn test_process_maskfile_with_test_files(#[case] filename: &str, #[case] expected: u32) {
+        let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let test_dir = manifest_dir.join("test");
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let context = ProcessCommandContext {
+            out_dir: temp_dir.path().to_path_buf(),
+            dump: None,
+            print_results: true,
+            no_warnings: false,
+            options: LintOptions::default(),
+            maskfile_label: None,
+            project_dir: None,
+            progress: false,
+        };
+
+        let maskfile_path = test_dir.join(filename);
+        assert!(maskfile_path.exists(), "Maskfile {filename} does not exist");
+        let total_findings = process_maskfile(&maskfile_path, &context);
+        assert!(total_findings.is_ok(), "process_maskfile should succeed for test/{filename}.md");
+        assert_eq!(total_findings.unwrap().findings, expected);
+    }
+
+    #[test]
+    fn test_lint_maskfile() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let maskfile_path = temp_dir.path().join("maskfile.md");
+        fs::write(
+            &maskfile_path,
+            "## build\n\n```bat\ngoto missing\n```\n\n## test\n\n```bat\necho fine\n```\n",
+        )
+        .unwrap();
+
+        let reports = lint_maskfile(&maskfile_path, &LintOptions::default()).unwrap();
+        let statuses: Vec<(&str, CommandStatus)> =
+            reports.iter().map(|r| (r.command.as_str(), r.status)).collect();
+        assert_eq!(
+            statuses,
+            vec![("build", CommandStatus::Failed), ("test", CommandStatus::Passed)]
+        );
+        assert_eq!(reports[0].linters, vec!["batch"]);
+    }
+
+    #[test]
+    fn test_lint_maskfile_structure() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let maskfile_path = temp_dir.path().join("maskfile.md");
+        fs::write(
+            &maskfile_path,
+            "## build\n\n```bat\necho\n```\n\n#### build test\n\n```bat\necho\n```\n",
+        )
+        .unwrap();
+
+        let reports = lint_maskfile(&maskfile_path, &LintOptions::default()).unwrap();
+        let report = reports.last().unwrap();
+        assert_eq!(report.command, structure::LINTER);
+        assert_eq!(report.status, CommandStatus::Failed);
+        assert_eq!(report.findings[0].rule.as_deref(), Some("MF005"));
+        assert_eq!(report.findings[0].line, Some(7));
+
+        let mut options = LintOptions::default();
+        options.config.linters.insert(
+            structure::LINTER.to_string(),
+            config::LinterConfig { enabled: Some(false), ..Default::default() },
+        );
+        assert_eq!(lint_maskfile(&maskfile_path, &options).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_dump() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let context = ProcessCommandContext {
+            out_dir: temp_dir.path().to_path_buf(),
+            dump: Some(DumpOptions { layout: dump::Layout::Nested, ..DumpOptions::default() }),
+            print_results: false,
+            no_warnings: false,
+            options: LintOptions::default(),
+            maskfile_label: None,
+            project_dir: None,
+            progress: false,
+        };
+        let content = "# Tasks\n\n## services\n\n### services api\n\n```bash\necho api\n```\n";
+        process_maskfile_content(content.to_string(), "maskfile.md", &context).unwrap();
+
+        let script = fs::read_to_string(temp_dir.path().join("services/api.sh")).unwrap();
+        assert!(script.starts_with(
+            "# Dumped by masklint from maskfile.md\n# Command: services api\n# Lines: 7-9\n"
+        ));
+        assert!(script.ends_with("echo api\n"));
+        // the variables are only declared for linting
+        assert!(!script.contains("export"));
+        let manifest = fs::read_to_string(temp_dir.path().join(dump::MANIFEST_FILE_NAME)).unwrap();
+        let manifest: serde_json::Value = serde_json::from_str(&manifest).unwrap();
+        assert_eq!(
+            manifest["scripts"][0],
+            serde_json::json!({
+                "file": "services/api.sh",
+                "maskfile": "maskfile.md",
+                "command": "services api",
+                "executor": "bash",
+                "lines": { "start": 7, "end": 9 },
+            })
+        );
+
+        // dumping again fails unless the previous files are removed or overwritten
+        assert!(process_maskfile_content(content.to_string(), "maskfile.md", &context).is_err());
+        let context = ProcessCommandContext {
+            dump: Some(DumpOptions { clean: true, ..DumpOptions::default() }),
+            ..context
+        };
+        fs::write(temp_dir.path().join("notes.txt"), "keep").unwrap();
+        let content = "# Tasks
+
+## build
+
+```sh
+echo build
+```
+";
+        process_maskfile_content(content.to_string(), "maskfile.md", &context).unwrap();
+        assert!(!temp_dir.path().join("services").exists());
+        assert!(temp_dir.path().join("build.sh").exists());
+        assert!(temp_dir.path().join("notes.txt").exists());
+    }
+
+    #[test]
+    fn test_dump_filter() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let context = ProcessCommandContext {
+            out_dir: temp_dir.path().to_path_buf(),
+            dump: Some(DumpOptions {
+                commands: vec!["deploy*".to_string()],
+                executors: vec!["bash".to_string(), "python".to_string()],
+                ..DumpOptions::default()
+            }),
+            print_results: false,
+            no_warnings: false,
+            options: LintOptions::default(),
+            maskfile_label: None,
+            project_dir: None,
+            progress: false,
+        };
+        let content = "## build
+
+```bash
+echo
+```
+
+## deploy
+
+```bash
+echo
+```
+
+### deploy check
+
+```python3
+print()
+```
+
+### deploy notify
+
+```ruby
+puts
+```
+";
+        process_maskfile_content(content.to_string(), "maskfile.md", &context).unwrap();
+
+        let mut files: Vec<String> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        assert_eq!(files, vec!["deploy.sh", "deploy_check.py", dump::MANIFEST_FILE_NAME]);
+    }
+
+    #[test]
+    fn test_unique_file_names() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let context = ProcessCommandContext {
+            out_dir: temp_dir.path().to_path_buf(),
+            dump: None,
+            print_results: false,
+            no_warnings: false,
+            options: LintOptions::default(),
+            maskfile_label: None,
+            project_dir: None,
+            progress: false,
+        };
+        let content = "## db
+
+### db migrate
+
+```bash
+echo
+```
+
+## db_migrate
+
+```bash
+echo
+```
+
+## db_migrate
+
+```bash
+echo
+```
+
+## deploy
+
+```bash
+echo
+```
+";
+        let scripts = parse_scripts(content.to_string(), &context).unwrap();
+        let names: BTreeSet<&str> = scripts.iter().map(|s| s.file_name.as_str()).collect();
+        assert_eq!(names.len(), 4);
+        assert!(names.contains("deploy.sh"));
+        assert!(scripts[0].file_name.starts_with("db_migrate-"));
+        assert!(scripts[0].file_path.ends_with(&scripts[0].file_name));
+        // the names don't change between runs
+        let again = parse_scripts(content.to_string(), &context).unwrap();
+        assert_eq!(again[1].file_name, scripts[1].file_name);
+
+        // dumping them doesn't fail on existing files
+        let context = ProcessCommandContext { dump: Some(DumpOptions::default()), ..context };
+        process_maskfile_content(content.to_string(), "maskfile.md", &context).unwrap();
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 5);
+    }
+
+    #[test]
+    fn test_dump_windows_variants() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let context = ProcessCommandContext {
+            out_dir: temp_dir.path().to_path_buf(),
+            dump: Some(DumpOptions::default()),
+            print_results: false,
+            no_warnings: false,
+            options: LintOptions::default(),
+            maskfile_label: None,
+            project_dir: None,
+            progress: false,
+        };
+        let content = "## build (target)
+
+```bash
+make \"$target\"
+```
+
+```powershell
+msbuild $env:target
+```
+
+## clean
+
+```cmd
+del out
+```
+";
+        let scripts = parse_scripts(content.to_string(), &context).unwrap();
+        let labels: Vec<String> = scripts.iter().map(ExtractedScript::label).collect();
+        assert_eq!(labels, ["build (linux)", "build (windows)", "clean (windows)"]);
+        assert_eq!(scripts[1].fence, Some(Fence { start: 6, end: 8 }));
+        assert!(scripts[1].content.contains("msbuild $env:target"));
+
+        process_maskfile_content(content.to_string(), "maskfile.md", &context).unwrap();
+        let mut files: Vec<String> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        assert_eq!(
+            files,
+            ["build.linux.sh", "build.windows.ps1", "clean.windows.bat", dump::MANIFEST_FILE_NAME]
+        );
+    }
+
+    #[test]
+    fn test_script_fences() {
+        let context = ProcessCommandContext {
+            out_dir: PathBuf::from("/tmp/masklint"),
+            dump: None,
+            print_results: false,
+            no_warnings: false,
+            options: LintOptions::default(),
+            maskfile_label: None,
+            project_dir: None,
+            progress: false,
+        };
+        // the indented block isn't found, and `test` is defined twice
+        let content = "## lint
+
+    cargo clippy
+
+## build
+
+```sh
+cargo build
+```
+
+## test
+
+```sh
+cargo test
+```
+
+## test
+
+```sh
+cargo nextest run
+```
+";
+        let scripts = parse_scripts(content.to_string(), &context).unwrap();
+        let fences: Vec<(&str, Option<Fence>)> =
+            scripts.iter().map(|s| (s.command.as_str(), s.fence)).collect();
+        assert_eq!(
+            fences,
+            [
+                ("lint", None),
+                ("build", Some(Fence { start: 6, end: 8 })),
+                ("test", None),
+                ("test", None)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_process_only_changed() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let context = ProcessCommandContext {
+            out_dir: temp_dir.path().to_path_buf(),
+            dump: None,
+            print_results: false,
+            no_warnings: false,
+            options: LintOptions::default(),
+            maskfile_label: None,
+            project_dir: None,
+            progress: false,
+        };
+        let previous = "## build\n\n```bat\ngoto missing\n```\n\n## test\n\n```bat\necho\n```\n";
+        let content = previous.replace("echo", "goto other");
+
+        let summary =
+            process(content, Some(previous.to_string()), "maskfile.md", &context).unwrap();
+        let commands: Vec<&str> = summary.reports.iter().map(|r| r.command.as_str()).collect();
+        assert_eq!(commands, vec!["test"]);
+
+        // rejected before stdin is read
+        let options = LintOptions { changed_since: Some("HEAD".to_string()), ..context.options };
+        let context = ProcessCommandContext { options, ..context };
+        assert!(process_maskfile(Path::new("-"), &context).is_err());
+    }
+
+    #[test]
+    fn test_check_linter_versions() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let mut context = ProcessCommandContext {
+            out_dir: temp_dir.path().to_path_buf(),
+            dump: None,
+            print_results: false,
+            no_warnings: false,
+            options: LintOptions::default(),
+            maskfile_label: None,
+            project_dir: None,
+            progress: false,
+        };
+        // the wrapper gets --version appended, which sh passes on as $1
+        let ruff = "command = [\"sh\", \"-c\", \"echo ruff 0.3.1\", \"--\"]\nmin_version = \"0.4\"";
+        context.options.config.linters.insert("ruff".to_string(), toml::from_str(ruff).unwrap());
+        let scripts =
+            parse_scripts("## build\n\n```python\nprint(1)\n```\n".to_string(), &context).unwrap();
+
+        let error = check_linter_versions(&context, &scripts, &mut Summary::default()).unwrap_err();
+        assert_eq!(error.to_string(), "ruff is older than the required 0.4: ruff 0.3.1");
+
+        context.options.config.allow_outdated_linters = true;
+        let mut summary = Summary::default();
+        check_linter_versions(&context, &scripts, &mut summary).unwrap();
+        assert_eq!(summary.outdated_linters.len(), 1);
+
+        context.options.config.linters.get_mut("ruff").unwrap().min_version =
+            Some("0.3".to_string());
+        let mut summary = Summary::default();
+        check_linter_versions(&context, &scripts, &mut summary).unwrap();
+        assert!(summary.outdated_linters.is_empty());
+    }
+
+    #[test]
+    fn test_process_keep_going() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let mut context = ProcessCommandContext {
+            out_dir: temp_dir.path().to_path_buf(),
+            dump: None,
+            print_results: false,
+            no_warnings: false,
+            options: LintOptions { keep_going: true, ..LintOptions::default() },
+            maskfile_label: None,
+            project_dir: None,
+            progress: false,
+        };
+        context.options.config.linters.insert(
+            "rubocop".to_string(),
+            toml::from_str("command = [\"masklint-missing-rubocop\"]").unwrap(),
+        );
+        let content = "## build\n\n```ruby\nputs 1\n```\n\n## test\n\n```bat\necho\n```\n";
+
+        let summary = process(content.to_string(), None, "maskfile.md", &context).unwrap();
+        let statuses: Vec<(&str, CommandStatus)> =
+            summary.reports.iter().map(|r| (r.command.as_str(), r.status)).collect();
+        assert_eq!(
+            statuses,
+            vec![("build", CommandStatus::Failed), ("test", CommandStatus::Passed)]
+        );
+        assert_eq!(summary.errors.len(), 1);
+        assert!(summary.errors[0].chain().any(|e| e.is::<MissingLinter>()));
+        assert_eq!(
+            format!("{:#}", summary.errors[0]),
+            "build: executable for rubocop not found in $PATH"
+        );
+
+        // the script files are never overwritten
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        context.out_dir = temp_dir.path().to_path_buf();
+        context.options.keep_going = false;
+        let result = process(content.to_string(), None, "maskfile.md", &context);
+        assert!(result.is_err_and(|e| e.chain().any(|e| e.is::<MissingLinter>())));
+    }
+}
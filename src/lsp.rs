@@ -0,0 +1,242 @@
+use std::{collections::HashMap, sync::Mutex};
+use tower_lsp::{
+    jsonrpc,
+    lsp_types::{
+        Diagnostic, DiagnosticSeverity, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+        DidOpenTextDocumentParams, InitializeParams, InitializeResult, InitializedParams,
+        MessageType, NumberOrString, Position, Range, ServerCapabilities, ServerInfo,
+        TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+    },
+    Client, LanguageServer, LspService, Server,
+};
+
+use masklint::{
+    findings::Severity,
+    process_maskfile_content,
+    report::{CommandReport, CommandStatus},
+    ProcessCommandContext,
+};
+
+/// Runs a language server over stdin and stdout that lints the open
+/// maskfiles on every change and publishes the findings as diagnostics.
+pub fn serve(context: &ProcessCommandContext) -> anyhow::Result<()> {
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async {
+        let (service, socket) = LspService::new(|client| Backend {
+            client,
+            context: context.clone(),
+            versions: Mutex::default(),
+        });
+        Server::new(tokio::io::stdin(), tokio::io::stdout(), socket).serve(service).await;
+    });
+    // the thread that reads stdin is still blocked and would keep the process alive
+    runtime.shutdown_background();
+    Ok(())
+}
+
+struct Backend {
+    client: Client,
+    context: ProcessCommandContext,
+    /// The latest version of every open document, results for older versions
+    /// are dropped since linting them might finish after the newer ones.
+    versions: Mutex<HashMap<Url, i32>>,
+}
+
+#[tower_lsp::async_trait]
+impl LanguageServer for Backend {
+    async fn initialize(&self, _: InitializeParams) -> jsonrpc::Result<InitializeResult> {
+        Ok(InitializeResult {
+            capabilities: ServerCapabilities {
+                text_document_sync: Some(TextDocumentSyncCapability::Kind(
+                    TextDocumentSyncKind::FULL,
+                )),
+                ..ServerCapabilities::default()
+            },
+            server_info: Some(ServerInfo {
+                name: "masklint".to_string(),
+                version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            }),
+        })
+    }
+
+    async fn initialized(&self, _: InitializedParams) {
+        self.client.log_message(MessageType::INFO, "masklint is ready").await;
+    }
+
+    async fn shutdown(&self) -> jsonrpc::Result<()> {
+        Ok(())
+    }
+
+    async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        let document = params.text_document;
+        self.lint(document.uri, document.text, document.version).await;
+    }
+
+    async fn did_change(&self, mut params: DidChangeTextDocumentParams) {
+        // only full syncs are supported, so the last change is the whole document
+        if let Some(change) = params.content_changes.pop() {
+            let document = params.text_document;
+            self.lint(document.uri, change.text, document.version).await;
+        }
+    }
+
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        let uri = params.text_document.uri;
+        self.versions.lock().unwrap_or_else(|e| e.into_inner()).remove(&uri);
+        self.client.publish_diagnostics(uri, vec![], None).await;
+    }
+}
+
+impl Backend {
+    async fn lint(&self, uri: Url, content: String, version: i32) {
+        self.versions.lock().unwrap_or_else(|e| e.into_inner()).insert(uri.clone(), version);
+        let context = self.context.clone();
+        let name = uri.path().rsplit('/').next().unwrap_or_default().to_string();
+        // unsaved documents are linted like the saved maskfile in its project
+        let project_dir = uri.to_file_path().ok().and_then(|p| p.parent().map(|d| d.to_path_buf()));
+        // the linters are blocking processes, so they're run outside of the runtime
+        let result = tokio::task::spawn_blocking(move || {
+            // every run gets a fresh dir since the script files are never overwritten
+            let tmp_dir = tempfile::tempdir()?;
+            let context = ProcessCommandContext {
+                out_dir: tmp_dir.path().to_path_buf(),
+                project_dir: project_dir.or(context.project_dir.clone()),
+                ..context
+            };
+            let summary = process_maskfile_content(content.clone(), &name, &context)?;
+            Ok::<_, anyhow::Error>(diagnostics(&content, &summary.reports, context.no_warnings))
+        })
+        .await;
+
+        let latest =
+            self.versions.lock().unwrap_or_else(|e| e.into_inner()).get(&uri) == Some(&version);
+        if !latest {
+            return;
+        }
+        match result {
+            Ok(Ok(diagnostics)) => {
+                self.client.publish_diagnostics(uri, diagnostics, Some(version)).await;
+            }
+            Ok(Err(e)) => self.client.show_message(MessageType::ERROR, format!("{e:#}")).await,
+            Err(e) => self.client.show_message(MessageType::ERROR, e.to_string()).await,
+        }
+    }
+}
+
+/// Maps the findings of every command to the lines in the maskfile. The
+/// findings of a script that couldn't be found reliably are shown at the top.
+fn diagnostics(content: &str, reports: &[CommandReport], no_warnings: bool) -> Vec<Diagnostic> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut diagnostics = vec![];
+    for report in reports {
+        if report.status == CommandStatus::Warning && !no_warnings {
+            diagnostics.push(Diagnostic {
+                range: line_range(&lines, report.fence.map_or(0, |f| f.start), None),
+                severity: Some(DiagnosticSeverity::WARNING),
+                source: Some("masklint".to_string()),
+                message: format!("{}: {}", report.command, report.output),
+                ..Diagnostic::default()
+            });
+        }
+        for finding in &report.findings {
+            let line = report.maskfile_line(finding).map_or(0, |line| line.saturating_sub(1));
+            diagnostics.push(Diagnostic {
+                range: line_range(&lines, line, finding.column),
+                severity: Some(match finding.severity {
+                    Severity::Error => DiagnosticSeverity::ERROR,
+                    Severity::Warning => DiagnosticSeverity::WARNING,
+                    Severity::Info => DiagnosticSeverity::INFORMATION,
+                    Severity::Style => DiagnosticSeverity::HINT,
+                }),
+                code: finding.rule.clone().map(NumberOrString::String),
+                source: Some(report.linters.join(", ")),
+                message: finding.message.clone(),
+                ..Diagnostic::default()
+            });
+        }
+    }
+    diagnostics
+}
+
+/// Spans from the column to the end of the line, both 1-based columns and
+/// 0-based lines are passed.
+fn line_range(lines: &[&str], line: usize, column: Option<usize>) -> Range {
+    let length = lines.get(line).map_or(0, |l| l.encode_utf16().count());
+    let start = column.map_or(0, |c| c.saturating_sub(1).min(length));
+    Range {
+        start: Position::new(line as u32, start as u32),
+        end: Position::new(line as u32, length as u32),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use masklint::{fences::Fence, findings::Finding, structure};
+
+    const MASKFILE: &str = "# Tasks
+
+## build
+
+```bash
+echo $1
+```
+
+## docs
+
+> no script
+
+### docs serve
+
+~~~sh
+# not a heading
+python -m http.server
+~~~
+";
+
+    #[test]
+    fn test_diagnostics() {
+        let report = |command: &str, findings| CommandReport {
+            maskfile: "maskfile.md".to_string(),
+            command: command.to_string(),
+            status: CommandStatus::Failed,
+            linters: vec!["shellcheck".to_string()],
+            findings,
+            output: String::new(),
+            fence: None,
+        };
+        let finding = Finding {
+            line: Some(1),
+            column: Some(6),
+            rule: Some("SC2086".to_string()),
+            severity: Severity::Info,
+            message: "Double quote to prevent globbing".to_string(),
+        };
+        let structure_finding = Finding {
+            line: Some(3),
+            column: None,
+            rule: Some("MF003".to_string()),
+            severity: Severity::Info,
+            message: "required argument".to_string(),
+        };
+        let structure_report = CommandReport {
+            linters: vec![structure::LINTER.to_string()],
+            ..report(structure::LINTER, vec![structure_finding])
+        };
+        let build = CommandReport {
+            fence: Some(Fence { start: 4, end: 6 }),
+            ..report("build", vec![finding.clone()])
+        };
+        let reports = vec![build, report("docs serve", vec![]), structure_report];
+        let diagnostics = diagnostics(MASKFILE, &reports, false);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].range, Range::new(Position::new(5, 5), Position::new(5, 7)));
+        assert_eq!(diagnostics[0].code, Some(NumberOrString::String("SC2086".to_string())));
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::INFORMATION));
+        assert_eq!(diagnostics[1].range, Range::new(Position::new(2, 0), Position::new(2, 8)));
+
+        // without a reliable fence the finding is shown at the top
+        let unreliable = super::diagnostics(MASKFILE, &[report("build", vec![finding])], false);
+        assert_eq!(unreliable[0].range, Range::new(Position::new(0, 5), Position::new(0, 7)));
+    }
+}
@@ -1,26 +1,137 @@
 use anyhow::anyhow;
-use clap::{command, Parser, Subcommand};
-use owo_colors::OwoColorize;
+use clap::{command, error::ErrorKind, CommandFactory, Parser, Subcommand, ValueEnum};
+use owo_colors::{Stream, Style};
 use std::{
-    fs::{self, File},
-    io::{self, Write},
-    path::PathBuf,
+    env, fs,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::{Duration, Instant},
 };
 
-mod handlers;
-use handlers::{Catchall, LanguageHandler, LintResultType, Nushell, Rubocop, Ruff, Shellcheck};
+use masklint::{
+    baseline::{self, Baseline},
+    cache::Cache,
+    color::{self, paint, ColorChoice},
+    config::{self, Config},
+    dump::{self, DumpOptions, Layout},
+    findings::Severity,
+    handlers::{FailedDaemons, Runner},
+    install::{self, Tool},
+    is_stdin, process_maskfiles, report,
+    report::{Format, GroupBy},
+    LintOptions, MissingLinter, ProcessCommandContext, Summary,
+};
+
+mod explain;
+mod lsp;
+mod tui;
+mod watch;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
-    #[arg(global = true, long, default_value = "maskfile.md")]
-    /// Path to a different maskfile you want to use
-    maskfile: PathBuf,
+    #[arg(global = true, long)]
+    /// Path to a different maskfile you want to use, can be given multiple times.
+    /// Use "-" to read it from stdin. Defaults to $MASKFILE or the closest maskfile.md in the current or a parent dir
+    maskfile: Vec<PathBuf>,
+
+    #[arg(global = true, long)]
+    /// Path to the baseline of findings to ignore. Defaults to the closest .masklint-baseline.json in the current or a parent dir
+    baseline: Option<PathBuf>,
+
+    #[arg(global = true, long)]
+    /// Path to the config file. Defaults to the closest .masklint.toml in the current or a parent dir
+    config: Option<PathBuf>,
 
     #[arg(global = true, long)]
     /// Suppress warning messages
     no_warnings: bool,
 
+    #[arg(global = true, long, value_enum, default_value_t = Severity::Style)]
+    /// Minimum severity of findings that fail the run, less severe findings are only shown
+    fail_level: Severity,
+
+    #[arg(global = true, long)]
+    /// Treat warnings, e.g. for scripts without a linter, as lint failures
+    strict: bool,
+
+    #[arg(global = true, long)]
+    /// Skip linters that are not installed instead of aborting the run
+    allow_missing_linters: bool,
+
+    #[arg(global = true, long)]
+    /// Lint with the linters' defaults instead of the config of the project that contains the maskfile
+    isolated: bool,
+
+    #[arg(global = true, long)]
+    /// Run rubocop without its server, which otherwise keeps running in the background to speed up later runs
+    no_daemon: bool,
+
+    #[arg(global = true, long)]
+    /// Check the syntax of the yaml, json and toml blocks in the maskfile
+    include_data_blocks: bool,
+
+    #[arg(global = true, long)]
+    /// Continue with the other commands if one can't be linted, e.g. because its linter crashed, and list the errors at the end
+    keep_going: bool,
+
+    #[arg(global = true, long)]
+    /// Exit with 0 even if there are lint failures, errors still fail the run
+    exit_zero: bool,
+
+    #[arg(global = true, long, value_name = "N")]
+    /// Only fail if there are more than N findings in total
+    max_findings: Option<u32>,
+
+    #[arg(global = true, long, value_enum, default_value_t = Format::Human)]
+    /// Format of the lint report
+    format: Format,
+
+    #[arg(global = true, long, value_enum, default_value_t = GroupBy::Command)]
+    /// How the findings are grouped in the human format
+    group_by: GroupBy,
+
+    #[arg(global = true, long, value_name = "PATH")]
+    /// Write the report to a file instead, only a short summary is printed
+    output_file: Option<PathBuf>,
+
+    #[arg(global = true, long, value_name = "PATH")]
+    /// Additionally write a static HTML report with the details of every command, e.g. as a CI artifact
+    report_html: Option<PathBuf>,
+
+    #[arg(global = true, short, long, visible_alias = "summary-only")]
+    /// Only print whether each command passed or failed instead of the linter output
+    quiet: bool,
+
+    #[arg(global = true, long, value_name = "DIR", num_args = 0..=1)]
+    /// Cache the linter results of unchanged scripts. Defaults to masklint in the user's cache dir
+    cache_dir: Option<Option<PathBuf>>,
+
+    #[arg(global = true, long, value_name = "SECS")]
+    /// Kill linters that take longer and report it as a finding instead
+    timeout: Option<u64>,
+
+    #[arg(global = true, long, value_enum, default_value_t = Runner::Native)]
+    /// Where the linters are run, images can be configured per linter
+    runner: Runner,
+
+    #[arg(global = true, long, value_name = "REV")]
+    /// Only lint the commands whose scripts changed since the git revision
+    changed_since: Option<String>,
+
+    #[arg(global = true, long)]
+    /// Print how long each linter took per command at the end
+    timings: bool,
+
+    #[arg(global = true, short, long, action = clap::ArgAction::Count)]
+    /// Log the executed linters and their results to stderr, -vv also logs their output
+    verbose: u8,
+
+    #[arg(global = true, long, value_enum, default_value_t = ColorChoice::Auto)]
+    /// When to colorize the output
+    color: ColorChoice,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -28,27 +139,238 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Runs the linters.
-    Run {},
+    Run {
+        #[arg(short, long)]
+        /// Keep watching the maskfile and re-run the linters on every change
+        watch: bool,
+
+        #[arg(short, long, value_name = "DIR", num_args = 0..=1, default_missing_value = ".", conflicts_with = "maskfile")]
+        /// Lint all maskfiles found in the directory tree instead, respecting .gitignore
+        recursive: Option<PathBuf>,
+
+        #[arg(short, long, conflicts_with = "watch")]
+        /// Browse the findings in a terminal interface instead of printing them
+        interactive: bool,
+    },
     /// Extracts all the commands from the maskfile and dumps them as files
     /// into the defined directory.
     Dump {
-        #[arg(short, long)]
-        output: String,
+        #[arg(short, long, required_unless_present_any = ["stdout", "archive"])]
+        output: Option<String>,
+
+        #[arg(long, conflicts_with_all = ["output", "archive"])]
+        /// Print the scripts with a header each instead of writing them
+        stdout: bool,
+
+        #[arg(long, value_name = "PATH", conflicts_with = "output")]
+        /// Pack the scripts into an archive instead, e.g. out.tar.gz
+        archive: Option<PathBuf>,
+
+        #[arg(long, value_enum, default_value_t = Layout::Flat)]
+        /// How the scripts are organized in the output dir
+        layout: Layout,
+
+        #[arg(long)]
+        /// Overwrite existing files in the output dir
+        force: bool,
+
+        #[arg(long)]
+        /// Remove the files of the previous dump into the output dir first
+        clean: bool,
+
+        #[arg(long = "command", value_name = "PATTERN")]
+        /// Only dump the commands matching the glob pattern, e.g. "deploy*"
+        commands: Vec<String>,
+
+        #[arg(long = "executor", value_name = "EXECUTOR")]
+        /// Only dump the scripts with the executor, e.g. bash
+        executors: Vec<String>,
+    },
+    /// Records the current findings as a baseline, so only new findings fail
+    /// the following runs. Prints the baseline unless it is written to a file.
+    Baseline {
+        #[arg(short, long, value_name = "PATH", num_args = 0..=1, default_missing_value = baseline::BASELINE_FILE_NAME)]
+        /// Write the baseline to the file instead
+        write: Option<PathBuf>,
+    },
+    /// Lints the given maskfiles with one line per finding, meant to be run
+    /// by pre-commit with the staged maskfiles.
+    Hook {
+        /// The maskfiles to lint, nothing is linted if none are given
+        files: Vec<PathBuf>,
+    },
+    /// Runs a language server over stdin and stdout that publishes the
+    /// findings as diagnostics for the maskfiles open in an editor.
+    Lsp,
+    /// Explains a rule of any of the linters, e.g. SC2086, E501 or
+    /// Style/StringLiterals, using the linter that owns it.
+    Explain {
+        /// The rule code as it's shown in the findings
+        code: String,
+    },
+    /// Downloads pinned static builds of linters into a directory that is
+    /// searched before the PATH.
+    Install {
+        #[arg(value_enum)]
+        /// The linters to install, defaults to all
+        tools: Vec<Tool>,
     },
 }
 
-struct ProcessCommandContext {
-    out_dir: PathBuf,
-    is_dump: bool,
-    no_warnings: bool,
+/// Exit codes that let CI tell lint failures apart from a broken setup.
+/// Usage errors are reported by clap, which exits with 2 as well.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExitStatus {
+    Clean = 0,
+    Findings = 1,
+    Error = 2,
+    MissingLinter = 3,
+}
+
+impl From<ExitStatus> for ExitCode {
+    fn from(status: ExitStatus) -> Self {
+        ExitCode::from(status as u8)
+    }
 }
 
-fn main() -> anyhow::Result<()> {
+fn main() -> ExitCode {
     let cli = Cli::parse();
+    if let Err(e) = check_stdin_once(&cli) {
+        e.exit();
+    }
+    color::init(cli.color);
+    let level = match cli.verbose {
+        0 => log::LevelFilter::Warn,
+        1 => log::LevelFilter::Debug,
+        _ => log::LevelFilter::Trace,
+    };
+    // RUST_LOG still takes precedence for more fine grained filters
+    env_logger::Builder::new().filter_level(level).parse_default_env().init();
+    install::add_to_path();
+    match run(&cli) {
+        // the errors take precedence over the findings, even with --exit-zero
+        Ok(summary) if !summary.errors.is_empty() => {
+            summary.print_errors();
+            if summary.findings > 0 {
+                let failure_style = Style::new().bold().red();
+                eprintln!(
+                    "{}",
+                    paint(findings_summary(summary.findings), Stream::Stderr, failure_style)
+                );
+            }
+            match summary.errors.iter().all(|e| exit_status(e) == ExitStatus::MissingLinter) {
+                true => ExitStatus::MissingLinter.into(),
+                false => ExitStatus::Error.into(),
+            }
+        }
+        Ok(summary) if summary.findings > 0 => {
+            if let Some(max) = cli.max_findings.filter(|max| summary.failed_findings <= *max) {
+                let plural = if summary.failed_findings == 1 { "" } else { "s" };
+                let note = format!(
+                    "{} finding{plural} within the budget of {max}.",
+                    summary.failed_findings
+                );
+                eprintln!("{}", paint(note, Stream::Stderr, Style::new().yellow()));
+                return ExitStatus::Clean.into();
+            }
+            let failure_style = Style::new().bold().red();
+            eprintln!(
+                "{}",
+                paint(findings_summary(summary.findings), Stream::Stderr, failure_style)
+            );
+            if let Some(max) = cli.max_findings {
+                let note =
+                    format!("{} findings exceed the budget of {max}.", summary.failed_findings);
+                eprintln!("{}", paint(note, Stream::Stderr, failure_style));
+            }
+            if cli.exit_zero {
+                ExitStatus::Clean.into()
+            } else {
+                ExitStatus::Findings.into()
+            }
+        }
+        Ok(_) => ExitStatus::Clean.into(),
+        Err(e) => {
+            eprintln!("Error: {e:?}");
+            exit_status(&e).into()
+        }
+    }
+}
+
+fn exit_status(error: &anyhow::Error) -> ExitStatus {
+    if error.chain().any(|e| e.is::<MissingLinter>()) {
+        ExitStatus::MissingLinter
+    } else {
+        ExitStatus::Error
+    }
+}
+
+/// Stdin can only be read once, so it may only be given as one of the maskfiles.
+fn check_stdin_once(cli: &Cli) -> Result<(), clap::Error> {
+    if cli.maskfile.iter().filter(|p| is_stdin(p)).count() > 1 {
+        return Err(Cli::command()
+            .error(ErrorKind::ArgumentConflict, "'--maskfile -' can only be given once"));
+    }
+    Ok(())
+}
+
+fn run(cli: &Cli) -> anyhow::Result<Summary> {
+    if let Commands::Install { tools } = &cli.command {
+        let dir =
+            install::install_dir().ok_or_else(|| anyhow!("no data dir found for the user"))?;
+        let tools = if tools.is_empty() { Tool::value_variants() } else { tools };
+        for tool in tools {
+            let path = install::install(*tool, &dir)?;
+            println!("Installed {} {} to {}", tool.name(), tool.version(), path.display());
+        }
+        return Ok(Summary::default());
+    }
+    if let Commands::Explain { code } = &cli.command {
+        explain::explain(code)?;
+        return Ok(Summary::default());
+    }
+    let maskfiles = match &cli.command {
+        // clap only sees the conflict when --maskfile comes after the subcommand
+        Commands::Run { recursive: Some(_), .. } if !cli.maskfile.is_empty() => {
+            return Err(anyhow!("the maskfiles can't be given together with --recursive"));
+        }
+        Commands::Run { recursive: Some(dir), .. } => discover_maskfiles(dir)?,
+        // the editor sends the maskfiles
+        Commands::Lsp => vec![],
+        Commands::Hook { files } => files.clone(),
+        _ if cli.maskfile.is_empty() => vec![default_maskfile()?],
+        _ => cli.maskfile.clone(),
+    };
+    let config_path = match &cli.config {
+        Some(path) => Some(path.clone()),
+        None => find_upwards(&env::current_dir()?, config::CONFIG_FILE_NAME),
+    };
+    let config = match config_path {
+        Some(path) => Config::load(&path)?,
+        None => Config::default(),
+    };
+    let baseline_path = match &cli.baseline {
+        Some(path) => Some(path.clone()),
+        None => find_upwards(&env::current_dir()?, baseline::BASELINE_FILE_NAME),
+    };
+    let baseline = match baseline_path {
+        // the new baseline has to contain all findings
+        _ if matches!(cli.command, Commands::Baseline { .. }) => Baseline::default(),
+        Some(path) => Baseline::load(&path)?,
+        None => Baseline::default(),
+    };
+
+    let cache = match &cli.cache_dir {
+        Some(Some(dir)) => Some(Cache::new(dir.clone())),
+        Some(None) => Some(Cache::new(
+            Cache::default_dir().ok_or_else(|| anyhow!("no cache dir found for the user"))?,
+        )),
+        None => None,
+    };
 
     // keeping the _tmp dir here to not let it go out of scope
     let (out_dir, _tmp) = match &cli.command {
-        Commands::Dump { output } => {
+        Commands::Dump { output: Some(output), .. } => {
             let dir: PathBuf = output.parse()?;
             fs::create_dir_all(&dir)?;
             (dir, None)
@@ -58,123 +380,225 @@ fn main() -> anyhow::Result<()> {
             (tmp_dir.path().to_path_buf(), Some(tmp_dir))
         }
     };
+    let interactive = matches!(cli.command, Commands::Run { interactive: true, .. });
+    if interactive && !io::stdout().is_terminal() {
+        return Err(anyhow!("the findings can only be browsed interactively in a terminal"));
+    }
+    let human_output = matches!(cli.command, Commands::Run { interactive: false, .. })
+        && cli.format == Format::Human
+        && cli.output_file.is_none()
+        && !cli.quiet;
     let context = &ProcessCommandContext {
         out_dir,
-        is_dump: matches!(cli.command, Commands::Dump { .. }),
+        dump: match cli.command {
+            Commands::Dump {
+                layout, force, clean, ref commands, ref executors, stdout, ..
+            } => Some(DumpOptions {
+                layout,
+                force,
+                clean,
+                commands: commands.clone(),
+                executors: executors.clone(),
+                stdout,
+            }),
+            _ => None,
+        },
+        print_results: human_output && cli.group_by == GroupBy::Command,
         no_warnings: cli.no_warnings,
+        options: LintOptions {
+            fail_level: cli.fail_level,
+            strict: cli.strict,
+            allow_missing_linters: cli.allow_missing_linters || config.allow_missing_linters,
+            isolated: cli.isolated || config.isolated,
+            daemons: !(cli.no_daemon || config.no_daemon),
+            data_blocks: cli.include_data_blocks || config.include_data_blocks,
+            config,
+            baseline,
+            cache,
+            timeout: cli.timeout.map(Duration::from_secs),
+            runner: cli.runner,
+            changed_since: cli.changed_since.clone(),
+            keep_going: cli.keep_going,
+            failed_daemons: FailedDaemons::default(),
+        },
+        maskfile_label: None,
+        project_dir: None,
+        // the bar would be garbled by the logs and is useless in a pipe
+        progress: !cli.quiet
+            && cli.verbose == 0
+            && matches!(cli.command, Commands::Run { .. } | Commands::Baseline { .. })
+            && io::stderr().is_terminal(),
     };
 
-    let total_findings = process_maskfile(cli.maskfile, context)?;
-    if total_findings > 0 {
-        let plural = if total_findings == 1 { "" } else { "s" };
-        let error_msg = format!("{} file{} with lint failures.", total_findings, plural);
-        return Err(anyhow::anyhow!(error_msg.bold().red().to_string()));
+    if let Commands::Lsp = cli.command {
+        lsp::serve(context)?;
+        return Ok(Summary::default());
+    }
+    if let Commands::Run { watch: true, .. } = cli.command {
+        if maskfiles.iter().any(|p| is_stdin(p)) {
+            return Err(anyhow!("the maskfile can't be watched when reading it from stdin"));
+        }
+        if !context.print_results {
+            return Err(anyhow!(
+                "the maskfile can only be watched with the full output in the human format"
+            ));
+        }
+        watch::watch(&maskfiles, context)?;
+        return Ok(Summary::default());
     }
-    Ok(())
-}
-
-fn process_maskfile(
-    maskfile_path: PathBuf,
-    context: &ProcessCommandContext,
-) -> anyhow::Result<u32> {
-    let content = fs::read_to_string(maskfile_path)?;
-    let maskfile = mask_parser::parse(content);
 
-    let mut total_findings = 0;
-    for command in maskfile.commands {
-        total_findings += process_command(context, command, None)?;
+    let start = Instant::now();
+    let summary = process_maskfiles(&maskfiles, context)?;
+    summary.print_missing_linters();
+    summary.print_outdated_linters();
+    if cli.timings {
+        eprint!("{}", report::timings_table(&summary.timings));
     }
-    Ok(total_findings)
+    if let Commands::Baseline { write } = &cli.command {
+        let json = Baseline { findings: summary.fingerprints }.to_json()?;
+        match write {
+            Some(path) => {
+                fs::write(path, json)?;
+                println!("Wrote baseline to {}", path.display());
+            }
+            None => print!("{json}"),
+        }
+        return Ok(Summary::default());
+    }
+    if let Commands::Dump { archive: Some(path), .. } = &cli.command {
+        dump::archive(&context.out_dir, path)?;
+    }
+    if let Commands::Hook { .. } = cli.command {
+        print!("{}", report::render(Format::Compact, &summary.reports, cli.no_warnings));
+    }
+    if interactive {
+        tui::browse(&summary.reports)?;
+        return Ok(summary);
+    }
+    if let (Commands::Run { .. }, Some(path)) = (&cli.command, &cli.report_html) {
+        fs::write(path, report::render_html(&summary.reports))?;
+        eprintln!("Wrote the HTML report to {}", path.display());
+    }
+    if let Commands::Run { .. } = cli.command {
+        let report = || match (cli.format, cli.group_by) {
+            (Format::Human, GroupBy::Rule | GroupBy::Severity) => {
+                report::render_grouped(&summary.reports, cli.group_by, cli.no_warnings, false)
+            }
+            _ => report::render(cli.format, &summary.reports, cli.no_warnings),
+        };
+        match &cli.output_file {
+            Some(path) => {
+                fs::write(path, report())?;
+                let plural = if summary.reports.len() == 1 { "" } else { "s" };
+                let note = format!(
+                    "Linted {} command{plural}, wrote the report to {}",
+                    summary.reports.len(),
+                    path.display()
+                );
+                eprintln!("{note}");
+            }
+            None if cli.format != Format::Human => print!("{}", report()),
+            // the output per command was already printed while linting
+            None if cli.group_by == GroupBy::Command || cli.quiet => {}
+            None => print!(
+                "{}",
+                report::render_grouped(&summary.reports, cli.group_by, cli.no_warnings, true)
+            ),
+        }
+        if human_output {
+            let footer = report::footer(
+                &summary.reports,
+                cli.no_warnings,
+                summary.baselined,
+                start.elapsed(),
+            );
+            print!("{footer}");
+        }
+        // the table would mess up a machine readable report on stdout
+        if cli.quiet && (cli.format == Format::Human || cli.output_file.is_some()) {
+            print!("{}", report::summary_table(&summary.reports));
+        }
+    }
+    Ok(summary)
 }
 
-// Function to process a command and its subcommands
-fn process_command(
-    context: &ProcessCommandContext,
-    command: mask_parser::maskfile::Command,
-    parent_name: Option<&str>,
-) -> anyhow::Result<u32> {
-    // Build full command name including parent
-    let full_command_name = match parent_name {
-        Some(parent) => format!("{} {}", parent, command.name),
-        None => command.name,
-    };
+fn findings_summary(total_findings: u32) -> String {
+    let plural = if total_findings == 1 { "" } else { "s" };
+    format!("{total_findings} file{plural} with lint failures.")
+}
 
-    let mut findings_count = 0;
+/// Resolves the maskfile like mask itself does if none is given explicitly.
+fn default_maskfile() -> anyhow::Result<PathBuf> {
+    if let Some(path) = env::var_os("MASKFILE").filter(|p| !p.is_empty()) {
+        return Ok(PathBuf::from(path));
+    }
+    let cwd = env::current_dir()?;
+    find_upwards(&cwd, "maskfile.md").ok_or_else(|| {
+        anyhow!("no maskfile.md found in {} or any of its parent directories", cwd.display())
+    })
+}
 
-    if let Some(script) = command.script {
-        let language_handler: &dyn LanguageHandler = match script.executor.as_str() {
-            "sh" | "bash" => &Shellcheck {},
-            "py" | "python" => &Ruff {},
-            "rb" | "ruby" => &Rubocop {},
-            "nu" | "nushell" => &Nushell {},
-            _ => &Catchall {},
-        };
+fn find_upwards(dir: &Path, file_name: &str) -> Option<PathBuf> {
+    dir.ancestors().map(|d| d.join(file_name)).find(|p| p.is_file())
+}
 
-        let mut file_name = full_command_name.replace(" ", "_");
-        file_name.push_str(language_handler.file_extension());
-        let file_path = context.out_dir.join(&file_name);
-        let mut script_file = File::options().create_new(true).append(true).open(&file_path)?;
-        let content = language_handler.content(&script)?;
-        script_file.write_all(content.as_bytes())?;
-
-        if !context.is_dump {
-            let lint_result = language_handler.execute(&file_path).map_err(|e| match e.kind() {
-                io::ErrorKind::NotFound => {
-                    anyhow!("executable for {language_handler} not found in $PATH")
-                }
-                _ => anyhow!(e),
-            })?;
-            if !lint_result.message.is_empty() {
-                let print_results = || {
-                    println!("{}", full_command_name.bold().cyan().underline());
-                    println!("{}", lint_result.message);
-                };
-                match lint_result.result_type {
-                    LintResultType::Findings => {
-                        findings_count += 1;
-                        print_results();
-                    }
-                    LintResultType::Warning => {
-                        if !context.no_warnings {
-                            print_results();
-                        }
-                    }
-                }
-            }
+/// Finds all maskfiles in the directory tree, skipping anything that is
+/// excluded by .gitignore files.
+fn discover_maskfiles(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut maskfiles = vec![];
+    for entry in ignore::WalkBuilder::new(dir).require_git(false).build() {
+        let entry = entry?;
+        if entry.file_type().is_some_and(|t| t.is_file()) && entry.file_name() == "maskfile.md" {
+            maskfiles.push(entry.into_path());
         }
     }
-
-    // Process subcommands recursively
-    if !command.subcommands.is_empty() {
-        for subcmd in command.subcommands {
-            findings_count += process_command(context, subcmd, Some(&full_command_name))?;
-        }
+    if maskfiles.is_empty() {
+        return Err(anyhow!("no maskfile found in {}", dir.display()));
     }
-    Ok(findings_count)
+    maskfiles.sort();
+    Ok(maskfiles)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rstest::rstest;
-
-    #[rstest]
-    #[case("maskfile.md", 3)]
-    #[case("nested_maskfile.md", 2)]
-    fn test_process_maskfile_with_test_files(#[case] filename: &str, #[case] expected: u32) {
-        let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-        let test_dir = manifest_dir.join("test");
+    use std::fs::File;
+
+    #[test]
+    fn test_exit_status() {
+        let missing = anyhow::Error::new(MissingLinter("shellcheck".to_string()));
+        assert_eq!(exit_status(&missing), ExitStatus::MissingLinter);
+        assert_eq!(exit_status(&missing.context("while linting")), ExitStatus::MissingLinter);
+        assert_eq!(exit_status(&anyhow!("invalid config")), ExitStatus::Error);
+    }
+
+    #[test]
+    fn test_find_upwards() {
         let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
-        let context = ProcessCommandContext {
-            out_dir: temp_dir.path().to_path_buf(),
-            is_dump: false,
-            no_warnings: false,
-        };
+        let nested = temp_dir.path().join("a").join("b");
+        fs::create_dir_all(&nested).unwrap();
+        assert_eq!(find_upwards(&nested, "maskfile.md"), None);
+
+        let maskfile_path = temp_dir.path().join("maskfile.md");
+        File::create(&maskfile_path).unwrap();
+        assert_eq!(find_upwards(&nested, "maskfile.md"), Some(maskfile_path));
+    }
+
+    #[test]
+    fn test_check_stdin_once() {
+        let cli = Cli::try_parse_from(["masklint", "--maskfile", "-", "--maskfile", "a.md", "run"]);
+        assert!(check_stdin_once(&cli.unwrap()).is_ok());
+        let cli = Cli::try_parse_from(["masklint", "--maskfile", "-", "--maskfile", "-", "run"]);
+        assert!(check_stdin_once(&cli.unwrap()).is_err());
+    }
 
-        let maskfile_path = test_dir.join(filename);
-        assert!(maskfile_path.exists(), "Maskfile {} does not exist", filename);
-        let total_findings = process_maskfile(maskfile_path, &context);
-        assert!(total_findings.is_ok(), "process_maskfile should succeed for test/{}.md", filename);
-        assert_eq!(total_findings.unwrap(), expected);
+    #[test]
+    fn test_recursive_conflicts_with_maskfile() {
+        assert!(
+            Cli::try_parse_from(["masklint", "run", "--recursive", "--maskfile", "a.md"]).is_err()
+        );
+        let cli =
+            Cli::try_parse_from(["masklint", "--maskfile", "a.md", "run", "--recursive"]).unwrap();
+        assert!(run(&cli).is_err_and(|e| e.to_string().contains("--recursive")));
     }
 }
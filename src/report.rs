@@ -0,0 +1,966 @@
+use clap::ValueEnum;
+use owo_colors::{Stream, Style};
+use serde::Serialize;
+use serde_json::json;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    time::Duration,
+};
+
+use crate::{
+    color::paint,
+    fences::Fence,
+    findings::{Finding, Severity},
+    has_maskfile_lines,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// The linter output per command
+    Human,
+    Json,
+    /// SARIF 2.1.0, e.g. for GitHub code scanning
+    Sarif,
+    /// JUnit XML with a test case per command
+    Junit,
+    /// TAP version 13 with a test point per command
+    Tap,
+    /// One finding per line with its position in the maskfile, e.g. maskfile.md:42:7
+    Compact,
+    /// GitHub-flavored markdown, e.g. for PR comments or $GITHUB_STEP_SUMMARY
+    Markdown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GroupBy {
+    /// The linter output per command
+    Command,
+    /// A section per rule that lists the findings of all commands
+    Rule,
+    /// A section per severity, the most severe first
+    Severity,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommandStatus {
+    Passed,
+    Failed,
+    /// The command couldn't be linted, e.g. because there is no linter for it.
+    Warning,
+}
+
+/// The outcome of linting a single command.
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandReport {
+    pub maskfile: String,
+    pub command: String,
+    pub status: CommandStatus,
+    pub linters: Vec<String>,
+    pub findings: Vec<Finding>,
+    /// The combined output of the linters.
+    pub output: String,
+    /// Where the script is in the maskfile, if it was found reliably.
+    #[serde(skip)]
+    pub fence: Option<Fence>,
+}
+
+impl CommandReport {
+    /// Whether the output is shown in the human format at all.
+    pub fn is_shown(&self, no_warnings: bool) -> bool {
+        let hidden = no_warnings && self.status == CommandStatus::Warning;
+        !self.output.is_empty() && !hidden
+    }
+
+    /// The 1-based line of the finding in the maskfile, if it's known.
+    pub fn maskfile_line(&self, finding: &Finding) -> Option<usize> {
+        if has_maskfile_lines(self) {
+            return finding.line;
+        }
+        // the script starts in the line after the opening fence
+        self.fence.map(|fence| fence.start + 1 + finding.line.unwrap_or(1))
+    }
+}
+
+/// How long a single linter took for a command.
+#[derive(Debug, Clone)]
+pub struct Timing {
+    pub command: String,
+    pub linter: String,
+    pub duration: Duration,
+}
+
+/// Renders the reports of all linted commands in the given format.
+pub fn render(format: Format, reports: &[CommandReport], no_warnings: bool) -> String {
+    match format {
+        Format::Human => render_human(reports, no_warnings),
+        Format::Json => serde_json::to_string_pretty(reports).unwrap_or_default() + "\n",
+        Format::Sarif => render_sarif(reports),
+        Format::Junit => render_junit(reports),
+        Format::Tap => render_tap(reports),
+        Format::Compact => render_positions(reports, no_warnings),
+        Format::Markdown => render_markdown(reports, no_warnings),
+    }
+}
+
+/// Formats the output of a single command with its name as the header.
+pub fn human_section(report: &CommandReport, labeled: bool, colored: bool) -> String {
+    let header = if labeled {
+        format!("{}: {}", report.maskfile, report.command)
+    } else {
+        report.command.clone()
+    };
+    let header = if colored {
+        paint(header, Stream::Stdout, Style::new().bold().cyan().underline())
+    } else {
+        header
+    };
+    format!("{header}\n{}\n", report.output)
+}
+
+fn render_human(reports: &[CommandReport], no_warnings: bool) -> String {
+    let maskfiles: BTreeSet<&str> = reports.iter().map(|r| r.maskfile.as_str()).collect();
+    reports
+        .iter()
+        .filter(|r| r.is_shown(no_warnings))
+        .map(|r| human_section(r, maskfiles.len() > 1, false))
+        .collect()
+}
+
+/// Renders the human format with a section per rule or severity, so the same
+/// finding in many commands isn't repeated over and over. Commands with output
+/// but without findings, e.g. the ones without a linter, are listed last.
+pub fn render_grouped(
+    reports: &[CommandReport],
+    group_by: GroupBy,
+    no_warnings: bool,
+    colored: bool,
+) -> String {
+    if group_by == GroupBy::Command {
+        return render_human(reports, no_warnings);
+    }
+    let labeled = reports.iter().map(|r| r.maskfile.as_str()).collect::<BTreeSet<_>>().len() > 1;
+    // sorted by the position of the section and then its title
+    let mut sections: BTreeMap<(usize, String), Vec<String>> = BTreeMap::new();
+    for report in reports.iter().filter(|r| r.is_shown(no_warnings)) {
+        let name = if labeled {
+            format!("{}: {}", report.maskfile, report.command)
+        } else {
+            report.command.clone()
+        };
+        if report.findings.is_empty() {
+            let section = sections.entry((4, "without findings".to_string())).or_default();
+            section.push(format!("{name}: {}", report.output.trim()));
+        }
+        for finding in &report.findings {
+            let mut location = name.clone();
+            if let Some(line) = finding.line {
+                let _ = write!(location, ": line {line}");
+                if let Some(column) = finding.column {
+                    let _ = write!(location, ":{column}");
+                }
+            }
+            let (key, line) = match group_by {
+                GroupBy::Severity => {
+                    let rule = finding.rule.as_deref().map(|r| format!("{r} ")).unwrap_or_default();
+                    let key = (3 - finding.severity as usize, finding.severity.to_string());
+                    (key, format!("{location}: {rule}{}", finding.message))
+                }
+                _ => {
+                    let key = match &finding.rule {
+                        Some(rule) => (0, rule.clone()),
+                        None => (1, "without a rule".to_string()),
+                    };
+                    (key, format!("{location}: {}: {}", finding.severity, finding.message))
+                }
+            };
+            sections.entry(key).or_default().push(line);
+        }
+    }
+
+    let mut output = String::new();
+    for ((_, title), lines) in sections {
+        let plural = if lines.len() == 1 { "" } else { "s" };
+        let header = format!("{title} ({} time{plural})", lines.len());
+        let header = if colored {
+            paint(header, Stream::Stdout, Style::new().bold().cyan().underline())
+        } else {
+            header
+        };
+        let _ = writeln!(output, "{header}");
+        for line in lines {
+            let _ = writeln!(output, "  {line}");
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// Lists whether each command passed or failed, followed by the totals.
+pub fn summary_table(reports: &[CommandReport]) -> String {
+    let labeled = reports.iter().map(|r| r.maskfile.as_str()).collect::<BTreeSet<_>>().len() > 1;
+    let mut table = String::new();
+    for report in reports {
+        let (status, style) = match report.status {
+            CommandStatus::Passed => ("PASS", Style::new().green()),
+            CommandStatus::Failed => ("FAIL", Style::new().bold().red()),
+            CommandStatus::Warning => ("WARN", Style::new().yellow()),
+        };
+        let name = if labeled {
+            format!("{}: {}", report.maskfile, report.command)
+        } else {
+            report.command.clone()
+        };
+        let findings = match report.findings.len() {
+            0 => String::new(),
+            1 => " (1 finding)".to_string(),
+            n => format!(" ({n} findings)"),
+        };
+        let _ = writeln!(table, "{}  {name}{findings}", paint(status, Stream::Stdout, style));
+    }
+    let count = |status| reports.iter().filter(|r| r.status == status).count();
+    let _ = writeln!(
+        table,
+        "{} commands: {} passed, {} failed, {} warnings",
+        reports.len(),
+        count(CommandStatus::Passed),
+        count(CommandStatus::Failed),
+        count(CommandStatus::Warning)
+    );
+    table
+}
+
+/// Sums up the whole run below the output of the human format.
+pub fn footer(
+    reports: &[CommandReport],
+    no_warnings: bool,
+    baselined: u32,
+    elapsed: Duration,
+) -> String {
+    let count = |status| reports.iter().filter(|r| r.status == status).count();
+    let mut linters: BTreeMap<&str, usize> = BTreeMap::new();
+    for linter in reports.iter().flat_map(|r| &r.linters) {
+        *linters.entry(linter).or_default() += 1;
+    }
+    let mut linters: Vec<(&str, usize)> = linters.into_iter().collect();
+    linters.sort_by(|a, b| b.1.cmp(&a.1));
+    let linters = match linters.is_empty() {
+        true => "none".to_string(),
+        false => linters.iter().map(|(l, n)| format!("{l} ({n})")).collect::<Vec<_>>().join(", "),
+    };
+    let findings = [Severity::Error, Severity::Warning, Severity::Info, Severity::Style]
+        .map(|severity| {
+            let n = reports.iter().flat_map(|r| &r.findings).filter(|f| f.severity == severity);
+            format!("{} {severity}", n.count())
+        })
+        .join(", ");
+    let hidden = if no_warnings { count(CommandStatus::Warning) } else { 0 };
+
+    let rows = [
+        (
+            "Commands",
+            format!(
+                "{} linted: {} passed, {} failed, {} warnings",
+                reports.len(),
+                count(CommandStatus::Passed),
+                count(CommandStatus::Failed),
+                count(CommandStatus::Warning)
+            ),
+        ),
+        ("Linters", linters),
+        ("Findings", findings),
+        ("Suppressed", format!("{hidden} warnings, {baselined} findings in the baseline")),
+        ("Elapsed", format!("{elapsed:.2?}")),
+    ];
+    let mut footer = String::from("\n");
+    for (label, value) in rows {
+        let label = paint(format!("{label:<10}"), Stream::Stdout, Style::new().bold());
+        let _ = writeln!(footer, "{label}  {value}");
+    }
+    footer
+}
+
+/// Prints every finding as `maskfile.md:42:7: SC2086 warning: message`, which
+/// terminals and editors turn into links. Findings of scripts that couldn't be
+/// found in the maskfile point to its first line.
+fn render_positions(reports: &[CommandReport], no_warnings: bool) -> String {
+    let mut lines = String::new();
+    for report in reports {
+        if report.status == CommandStatus::Warning && report.is_shown(no_warnings) {
+            let line = report.fence.map_or(1, |f| f.start + 1);
+            let message = report.output.lines().next().unwrap_or_default();
+            let _ = writeln!(
+                lines,
+                "{}:{line}: warning: {}: {message}",
+                report.maskfile, report.command
+            );
+        }
+        for finding in &report.findings {
+            let _ =
+                write!(lines, "{}:{}", report.maskfile, report.maskfile_line(finding).unwrap_or(1));
+            if let Some(column) = finding.column {
+                let _ = write!(lines, ":{column}");
+            }
+            lines.push(':');
+            if let Some(rule) = &finding.rule {
+                let _ = write!(lines, " {rule}");
+            }
+            let _ = writeln!(lines, " {}: {}", finding.severity, finding.message);
+        }
+    }
+    lines
+}
+
+/// Sums up the timings per linter and per command, the slowest first.
+pub fn timings_table(timings: &[Timing]) -> String {
+    let mut per_linter: BTreeMap<&str, (usize, Duration)> = BTreeMap::new();
+    let mut per_command: BTreeMap<&str, Duration> = BTreeMap::new();
+    for timing in timings {
+        let linter = per_linter.entry(&timing.linter).or_default();
+        linter.0 += 1;
+        linter.1 += timing.duration;
+        *per_command.entry(&timing.command).or_default() += timing.duration;
+    }
+    let mut per_linter: Vec<_> = per_linter.into_iter().collect();
+    per_linter.sort_by(|a, b| b.1 .1.cmp(&a.1 .1));
+    let mut per_command: Vec<_> = per_command.into_iter().collect();
+    per_command.sort_by(|a, b| b.1.cmp(&a.1));
+
+    let width = timings
+        .iter()
+        .flat_map(|t| [t.linter.len(), t.command.len()])
+        .chain(["Command".len()])
+        .max()
+        .unwrap_or_default();
+    let mut table = String::new();
+    let _ = writeln!(table, "{:<width$}  {:>5}  {:>10}", "Linter", "Runs", "Total");
+    for (linter, (runs, total)) in per_linter {
+        let _ = writeln!(table, "{linter:<width$}  {runs:>5}  {:>10}", format!("{total:.2?}"));
+    }
+    let _ = writeln!(table, "\n{:<width$}  {:>5}  {:>10}", "Command", "", "Total");
+    for (command, total) in per_command {
+        let _ = writeln!(table, "{command:<width$}  {:>5}  {:>10}", "", format!("{total:.2?}"));
+    }
+    table
+}
+
+fn render_sarif(reports: &[CommandReport]) -> String {
+    let mut results = vec![];
+    for report in reports {
+        for finding in &report.findings {
+            let mut result = json!({
+                "level": sarif_level(finding.severity),
+                "message": { "text": finding.message },
+                "locations": [{
+                    "physicalLocation": { "artifactLocation": { "uri": report.maskfile } },
+                    "logicalLocations": [{ "name": report.command, "kind": "function" }],
+                }],
+                "properties": {
+                    "command": report.command,
+                    "scriptLine": finding.line,
+                    "scriptColumn": finding.column,
+                },
+            });
+            if let Some(line) = report.maskfile_line(finding) {
+                let mut region = json!({ "startLine": line });
+                if let Some(column) = finding.column {
+                    region["startColumn"] = json!(column);
+                }
+                result["locations"][0]["physicalLocation"]["region"] = region;
+            }
+            if let Some(rule) = &finding.rule {
+                result["ruleId"] = json!(rule);
+            }
+            results.push(result);
+        }
+    }
+    let sarif = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "masklint",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/brumhard/masklint",
+                },
+            },
+            "results": results,
+        }],
+    });
+    serde_json::to_string_pretty(&sarif).unwrap_or_default() + "\n"
+}
+
+fn sarif_level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info | Severity::Style => "note",
+    }
+}
+
+fn render_junit(reports: &[CommandReport]) -> String {
+    let failures = reports.iter().filter(|r| r.status == CommandStatus::Failed).count();
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuites name=\"masklint\" tests=\"{}\" failures=\"{failures}\">",
+        reports.len()
+    );
+    let maskfiles: BTreeSet<&str> = reports.iter().map(|r| r.maskfile.as_str()).collect();
+    for maskfile in maskfiles {
+        let cases: Vec<&CommandReport> =
+            reports.iter().filter(|r| r.maskfile == maskfile).collect();
+        let failures = cases.iter().filter(|r| r.status == CommandStatus::Failed).count();
+        let _ = writeln!(
+            xml,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{failures}\">",
+            escape_xml(maskfile),
+            cases.len()
+        );
+        for case in cases {
+            let _ = write!(
+                xml,
+                "    <testcase classname=\"{}\" name=\"{}\"",
+                escape_xml(maskfile),
+                escape_xml(&case.command)
+            );
+            let output = escape_xml(&case.output);
+            match case.status {
+                CommandStatus::Failed => {
+                    let plural = if case.findings.len() == 1 { "" } else { "s" };
+                    let _ = writeln!(
+                        xml,
+                        ">\n      <failure message=\"{} finding{plural}\">{output}</failure>\n    </testcase>",
+                        case.findings.len()
+                    );
+                }
+                _ if !case.output.is_empty() => {
+                    let _ = writeln!(
+                        xml,
+                        ">\n      <system-out>{output}</system-out>\n    </testcase>"
+                    );
+                }
+                _ => xml.push_str(" />\n"),
+            }
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+/// Commands without a linter are skipped, failed ones list their findings in
+/// a YAML block after the test point.
+fn render_tap(reports: &[CommandReport]) -> String {
+    let mut tap = format!("TAP version 13\n1..{}\n", reports.len());
+    for (i, report) in reports.iter().enumerate() {
+        let description = format!("{}: {}", report.maskfile, report.command);
+        // a # would start a directive
+        let description = description.replace('#', "\\#");
+        let first_line = report.output.lines().next().unwrap_or_default();
+        let _ = match report.status {
+            CommandStatus::Passed => writeln!(tap, "ok {} - {description}", i + 1),
+            CommandStatus::Warning => {
+                writeln!(tap, "ok {} - {description} # SKIP {first_line}", i + 1)
+            }
+            CommandStatus::Failed => writeln!(tap, "not ok {} - {description}", i + 1),
+        };
+        if report.status != CommandStatus::Failed {
+            continue;
+        }
+        // json strings are valid yaml and take care of the escaping
+        let quote = |s: &str| serde_json::to_string(s).unwrap_or_default();
+        tap.push_str("  ---\n");
+        let _ = writeln!(
+            tap,
+            "  linters: [{}]",
+            report.linters.iter().map(|l| quote(l)).collect::<Vec<_>>().join(", ")
+        );
+        if report.findings.is_empty() {
+            let _ = writeln!(tap, "  output: {}", quote(report.output.trim()));
+        } else {
+            tap.push_str("  findings:\n");
+        }
+        for finding in &report.findings {
+            let _ = writeln!(tap, "    - severity: {}", finding.severity);
+            if let Some(rule) = &finding.rule {
+                let _ = writeln!(tap, "      rule: {}", quote(rule));
+            }
+            if let Some(line) = finding.line {
+                let _ = writeln!(tap, "      line: {line}");
+            }
+            if let Some(column) = finding.column {
+                let _ = writeln!(tap, "      column: {column}");
+            }
+            let _ = writeln!(tap, "      message: {}", quote(&finding.message));
+        }
+        tap.push_str("  ...\n");
+    }
+    tap
+}
+
+/// Renders a table of all commands followed by a collapsed section with the
+/// findings and the output of every command that has any.
+fn render_markdown(reports: &[CommandReport], no_warnings: bool) -> String {
+    let count = |status| reports.iter().filter(|r| r.status == status).count();
+    let mut markdown = String::from("## masklint\n\n");
+    let _ = writeln!(
+        markdown,
+        "{} commands: {} passed, {} failed, {} warnings\n",
+        reports.len(),
+        count(CommandStatus::Passed),
+        count(CommandStatus::Failed),
+        count(CommandStatus::Warning)
+    );
+    markdown.push_str("| Maskfile | Command | Status | Findings |\n| --- | --- | --- | ---: |\n");
+    for report in reports {
+        let status = match report.status {
+            CommandStatus::Passed => "passed",
+            CommandStatus::Failed => "**failed**",
+            CommandStatus::Warning => "warning",
+        };
+        let _ = writeln!(
+            markdown,
+            "| {} | {} | {status} | {} |",
+            markdown_cell(&report.maskfile),
+            markdown_cell(&report.command),
+            report.findings.len()
+        );
+    }
+
+    for report in reports.iter().filter(|r| r.is_shown(no_warnings)) {
+        let _ = writeln!(
+            markdown,
+            "\n<details>\n<summary>{}: {} ({})</summary>\n",
+            escape_xml(&report.maskfile),
+            escape_xml(&report.command),
+            escape_xml(&report.linters.join(", "))
+        );
+        if !report.findings.is_empty() {
+            markdown.push_str("| Line | Severity | Rule | Message |\n| ---: | --- | --- | --- |\n");
+        }
+        for finding in &report.findings {
+            let line = report.maskfile_line(finding).map(|l| l.to_string()).unwrap_or_default();
+            let rule = finding.rule.as_deref().unwrap_or_default();
+            let _ = writeln!(
+                markdown,
+                "| {line} | {} | {} | {} |",
+                finding.severity,
+                markdown_cell(rule),
+                markdown_cell(&finding.message)
+            );
+        }
+        if !report.findings.is_empty() {
+            markdown.push('\n');
+        }
+        // the fence has to be longer than any run of backticks in the output
+        let longest = report.output.split(|c| c != '`').map(str::len).max().unwrap_or_default();
+        let fence = "`".repeat(longest.max(2) + 1);
+        let _ = writeln!(markdown, "{fence}\n{}\n{fence}\n</details>", report.output.trim_end());
+    }
+    markdown
+}
+
+/// Keeps the text in a single table cell.
+fn markdown_cell(s: &str) -> String {
+    s.replace('|', "\\|").replace('\n', " ")
+}
+
+const HTML_STYLE: &str = "body { font-family: sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; margin: 1em 0; }
+th, td { border: 1px solid #ddd; padding: 0.3em 0.6em; text-align: left; vertical-align: top; }
+th { background: #f4f4f4; }
+details { margin: 0.5em 0; }
+summary { cursor: pointer; font-weight: bold; }
+pre { background: #f7f7f7; padding: 0.8em; overflow-x: auto; }
+.badge { border-radius: 0.8em; padding: 0.1em 0.6em; font-size: 0.85em; color: #fff; }
+.passed { background: #2e7d32; } .failed, .error { background: #c62828; }
+.warning { background: #ef6c00; } .info { background: #1565c0; } .style { background: #6a1b9a; }";
+
+/// Renders a single static page without external resources, with a table of
+/// all commands followed by the details of the ones with output. The lines
+/// link to the maskfile like `maskfile.md#L42`.
+pub fn render_html(reports: &[CommandReport]) -> String {
+    let badge = |class: &str| format!("<span class=\"badge {class}\">{class}</span>");
+    let status = |report: &CommandReport| match report.status {
+        CommandStatus::Passed => "passed",
+        CommandStatus::Failed => "failed",
+        CommandStatus::Warning => "warning",
+    };
+    let count = |status| reports.iter().filter(|r| r.status == status).count();
+
+    let mut html =
+        String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    let _ = writeln!(
+        html,
+        "<title>masklint report</title>\n<style>\n{HTML_STYLE}\n</style>\n</head>\n<body>"
+    );
+    let _ = writeln!(html, "<h1>masklint report</h1>");
+    let _ = writeln!(
+        html,
+        "<p>{} commands: {} passed, {} failed, {} warnings</p>",
+        reports.len(),
+        count(CommandStatus::Passed),
+        count(CommandStatus::Failed),
+        count(CommandStatus::Warning)
+    );
+    html.push_str("<table>\n<tr><th>Maskfile</th><th>Command</th><th>Status</th><th>Linters</th><th>Findings</th></tr>\n");
+    for (i, report) in reports.iter().enumerate() {
+        let command = match report.output.is_empty() {
+            true => escape_xml(&report.command),
+            false => format!("<a href=\"#command-{i}\">{}</a>", escape_xml(&report.command)),
+        };
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td>{command}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape_xml(&report.maskfile),
+            badge(status(report)),
+            escape_xml(&report.linters.join(", ")),
+            report.findings.len()
+        );
+    }
+    html.push_str("</table>\n");
+
+    for (i, report) in reports.iter().enumerate().filter(|(_, r)| !r.output.is_empty()) {
+        let open = if report.status == CommandStatus::Failed { " open" } else { "" };
+        let _ = writeln!(
+            html,
+            "<details id=\"command-{i}\"{open}>\n<summary>{}: {} {}</summary>",
+            escape_xml(&report.maskfile),
+            escape_xml(&report.command),
+            badge(status(report))
+        );
+        if !report.findings.is_empty() {
+            html.push_str(
+                "<table>\n<tr><th>Line</th><th>Severity</th><th>Rule</th><th>Message</th></tr>\n",
+            );
+        }
+        for finding in &report.findings {
+            let line = match report.maskfile_line(finding) {
+                Some(line) => {
+                    format!("<a href=\"{}#L{line}\">{line}</a>", escape_xml(&report.maskfile))
+                }
+                None => String::new(),
+            };
+            let _ = writeln!(
+                html,
+                "<tr><td>{line}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                badge(&finding.severity.to_string()),
+                escape_xml(finding.rule.as_deref().unwrap_or_default()),
+                escape_xml(&finding.message)
+            );
+        }
+        if !report.findings.is_empty() {
+            html.push_str("</table>\n");
+        }
+        let _ = writeln!(html, "<pre>{}</pre>\n</details>", escape_xml(&report.output));
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(command: &str, status: CommandStatus, output: &str) -> CommandReport {
+        CommandReport {
+            maskfile: "maskfile.md".to_string(),
+            command: command.to_string(),
+            status,
+            linters: vec!["shellcheck".to_string()],
+            findings: vec![],
+            output: output.to_string(),
+            fence: None,
+        }
+    }
+
+    #[test]
+    fn test_render_junit() {
+        let reports = vec![
+            report("build", CommandStatus::Failed, "line 1: a < b"),
+            report("test", CommandStatus::Passed, ""),
+        ];
+        let xml = render_junit(&reports);
+        assert!(xml.contains("<testsuites name=\"masklint\" tests=\"2\" failures=\"1\">"));
+        assert!(xml.contains("<failure message=\"0 findings\">line 1: a &lt; b</failure>"));
+        assert!(xml.contains("<testcase classname=\"maskfile.md\" name=\"test\" />"));
+    }
+
+    #[test]
+    fn test_render_tap() {
+        let mut failed = report("build", CommandStatus::Failed, "In line 1: ...");
+        failed.findings.push(Finding {
+            line: Some(1),
+            column: Some(6),
+            rule: Some("SC2086".to_string()),
+            severity: Severity::Info,
+            message: "Double quote to prevent \"globbing\"".to_string(),
+        });
+        let reports = vec![
+            failed,
+            report("test", CommandStatus::Passed, ""),
+            report("docs", CommandStatus::Warning, "no linter for markdown"),
+            report("lint", CommandStatus::Failed, "ruff: timed out"),
+        ];
+        assert_eq!(
+            render_tap(&reports),
+            r#"TAP version 13
+1..4
+not ok 1 - maskfile.md: build
+  ---
+  linters: ["shellcheck"]
+  findings:
+    - severity: info
+      rule: "SC2086"
+      line: 1
+      column: 6
+      message: "Double quote to prevent \"globbing\""
+  ...
+ok 2 - maskfile.md: test
+ok 3 - maskfile.md: docs # SKIP no linter for markdown
+not ok 4 - maskfile.md: lint
+  ---
+  linters: ["shellcheck"]
+  output: "ruff: timed out"
+  ...
+"#
+        );
+    }
+
+    #[test]
+    fn test_render_positions() {
+        let finding = |line, column, rule: Option<&str>| Finding {
+            line: Some(line),
+            column,
+            rule: rule.map(str::to_string),
+            severity: Severity::Warning,
+            message: "Double quote to prevent globbing".to_string(),
+        };
+        let mut build = report("build", CommandStatus::Failed, "In line 2: ...");
+        build.fence = Some(Fence { start: 40, end: 44 });
+        build.findings = vec![finding(1, Some(7), Some("SC2086"))];
+        let mut lost = report("lost", CommandStatus::Failed, "line 3: ...");
+        lost.findings = vec![finding(3, None, None)];
+        let mut structure = report("maskfile", CommandStatus::Failed, "line 3: ...");
+        structure.linters = vec![crate::structure::LINTER.to_string()];
+        structure.findings = vec![finding(3, None, Some("MF003"))];
+        let mut docs = report("docs", CommandStatus::Warning, "no linter for markdown");
+        docs.fence = Some(Fence { start: 9, end: 11 });
+        let reports = vec![build, lost, structure, docs];
+
+        assert_eq!(
+            render(Format::Compact, &reports, false),
+            "maskfile.md:42:7: SC2086 warning: Double quote to prevent globbing
+maskfile.md:1: warning: Double quote to prevent globbing
+maskfile.md:3: MF003 warning: Double quote to prevent globbing
+maskfile.md:10: warning: docs: no linter for markdown
+"
+        );
+        assert!(!render(Format::Compact, &reports, true).contains("docs"));
+
+        let sarif: serde_json::Value =
+            serde_json::from_str(&render(Format::Sarif, &reports, false)).unwrap();
+        let regions: Vec<&serde_json::Value> = sarif["runs"][0]["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| &r["locations"][0]["physicalLocation"]["region"])
+            .collect();
+        assert_eq!(
+            regions,
+            [
+                &json!({ "startLine": 42, "startColumn": 7 }),
+                &serde_json::Value::Null,
+                &json!({ "startLine": 3 })
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let mut failed = report("build", CommandStatus::Failed, "In line 1:\n```a|b```");
+        failed.fence = Some(Fence { start: 4, end: 6 });
+        failed.findings.push(Finding {
+            line: Some(1),
+            column: Some(6),
+            rule: Some("SC2086".to_string()),
+            severity: Severity::Info,
+            message: "Double quote | globbing".to_string(),
+        });
+        let reports = vec![
+            failed,
+            report("test", CommandStatus::Passed, ""),
+            report("docs", CommandStatus::Warning, "no linter for markdown"),
+        ];
+        assert_eq!(
+            render(Format::Markdown, &reports, true),
+            "## masklint
+
+3 commands: 1 passed, 1 failed, 1 warnings
+
+| Maskfile | Command | Status | Findings |
+| --- | --- | --- | ---: |
+| maskfile.md | build | **failed** | 1 |
+| maskfile.md | test | passed | 0 |
+| maskfile.md | docs | warning | 0 |
+
+<details>
+<summary>maskfile.md: build (shellcheck)</summary>
+
+| Line | Severity | Rule | Message |
+| ---: | --- | --- | --- |
+| 6 | info | SC2086 | Double quote \\| globbing |
+
+````
+In line 1:
+```a|b```
+````
+</details>
+"
+        );
+    }
+
+    #[test]
+    fn test_render_html() {
+        let mut failed = report("build", CommandStatus::Failed, "In line 1: <error>");
+        failed.fence = Some(Fence { start: 4, end: 6 });
+        failed.findings.push(Finding {
+            line: Some(1),
+            column: Some(6),
+            rule: Some("SC2086".to_string()),
+            severity: Severity::Info,
+            message: "Double quote to prevent globbing".to_string(),
+        });
+        let reports = vec![failed, report("test", CommandStatus::Passed, "")];
+        let html = render_html(&reports);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<p>2 commands: 1 passed, 1 failed, 0 warnings</p>"));
+        assert!(html.contains("<td><a href=\"#command-0\">build</a></td>"));
+        assert!(html.contains("<td><a href=\"maskfile.md#L6\">6</a></td>"));
+        assert!(html.contains("<span class=\"badge info\">info</span>"));
+        assert!(html.contains("<pre>In line 1: &lt;error&gt;</pre>"));
+        assert!(!html.contains("command-1"));
+    }
+
+    #[test]
+    fn test_summary_table() {
+        let mut failed = report("build", CommandStatus::Failed, "line 1: error");
+        failed.findings.push(Finding::unparsed("error", Severity::Error));
+        let reports = vec![failed, report("test", CommandStatus::Passed, "")];
+        let table = summary_table(&reports);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].ends_with("  build (1 finding)"));
+        assert!(lines[1].ends_with("  test"));
+        assert_eq!(lines[2], "2 commands: 1 passed, 1 failed, 0 warnings");
+    }
+
+    #[test]
+    fn test_footer() {
+        let mut failed = report("build", CommandStatus::Failed, "line 1: error");
+        failed.findings.push(Finding::unparsed("error", Severity::Error));
+        let mut warning = report("docs", CommandStatus::Warning, "no linter");
+        warning.linters = vec![];
+        let reports = vec![failed, report("test", CommandStatus::Passed, ""), warning];
+        let footer = footer(&reports, true, 2, Duration::from_millis(1500));
+        assert_eq!(
+            footer,
+            "
+Commands    3 linted: 1 passed, 1 failed, 1 warnings
+Linters     shellcheck (2)
+Findings    1 error, 0 warning, 0 info, 0 style
+Suppressed  1 warnings, 2 findings in the baseline
+Elapsed     1.50s
+"
+        );
+    }
+
+    #[test]
+    fn test_render_grouped() {
+        let finding = |rule: &str, severity| Finding {
+            line: Some(2),
+            column: Some(6),
+            rule: Some(rule.to_string()),
+            severity,
+            message: "Double quote to prevent globbing".to_string(),
+        };
+        let mut build = report("build", CommandStatus::Failed, "In line 2: ...");
+        build.findings =
+            vec![finding("SC2086", Severity::Info), finding("SC2154", Severity::Warning)];
+        let mut deploy = report("deploy", CommandStatus::Failed, "In line 2: ...");
+        deploy.findings = vec![finding("SC2086", Severity::Info)];
+        let reports = vec![build, deploy, report("docs", CommandStatus::Warning, "no linter")];
+
+        assert_eq!(
+            render_grouped(&reports, GroupBy::Rule, false, false),
+            "SC2086 (2 times)
+  build: line 2:6: info: Double quote to prevent globbing
+  deploy: line 2:6: info: Double quote to prevent globbing
+
+SC2154 (1 time)
+  build: line 2:6: warning: Double quote to prevent globbing
+
+without findings (1 time)
+  docs: no linter
+
+"
+        );
+        let by_severity = render_grouped(&reports, GroupBy::Severity, true, false);
+        let headers: Vec<&str> = by_severity.lines().filter(|l| !l.starts_with(' ')).collect();
+        assert_eq!(headers, ["warning (1 time)", "", "info (2 times)", ""]);
+    }
+
+    #[test]
+    fn test_timings_table() {
+        let timing = |command: &str, linter: &str, millis| Timing {
+            command: command.to_string(),
+            linter: linter.to_string(),
+            duration: Duration::from_millis(millis),
+        };
+        let timings = vec![
+            timing("build", "shellcheck", 10),
+            timing("lint", "ruff", 30),
+            timing("test", "shellcheck", 5),
+        ];
+        let table = timings_table(&timings);
+        let lines: Vec<&str> = table.lines().map(str::trim_end).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "Linter       Runs       Total",
+                "ruff            1     30.00ms",
+                "shellcheck      2     15.00ms",
+                "",
+                "Command                 Total",
+                "lint                  30.00ms",
+                "build                 10.00ms",
+                "test                   5.00ms",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_human() {
+        let reports = vec![
+            report("build", CommandStatus::Failed, "line 1: error"),
+            report("test", CommandStatus::Passed, ""),
+            report("deploy", CommandStatus::Warning, "no linter"),
+        ];
+        assert_eq!(
+            render(Format::Human, &reports, false),
+            "build\nline 1: error\ndeploy\nno linter\n"
+        );
+        assert_eq!(render(Format::Human, &reports, true), "build\nline 1: error\n");
+    }
+}
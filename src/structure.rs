@@ -0,0 +1,505 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use mask_parser::maskfile::Script;
+
+use crate::{
+    config::Config,
+    fences::{closes_fence, command_name, heading, is_windows_only, opening_fence},
+    findings::{Finding, Severity},
+    handlers::parse_executor,
+};
+
+/// Name of the built-in rule set that checks the maskfile itself, it can be
+/// turned off in the config like any other linter.
+pub const LINTER: &str = "maskfile";
+
+/// Every rule with its severity and what it checks.
+pub const RULES: [(&str, Severity, &str); 7] = [
+    ("MF001", Severity::Error, "A command with the same name is already defined"),
+    ("MF002", Severity::Warning, "The command's code block is empty"),
+    ("MF003", Severity::Info, "A required argument isn't mentioned in the command's description"),
+    ("MF004", Severity::Error, "An entry in OPTIONS has no flags, so it can't be set"),
+    ("MF005", Severity::Warning, "A heading skips a level, e.g. `####` right after `##`"),
+    ("MF006", Severity::Warning, "An argument or option is never used in the command's script"),
+    (
+        "MF007",
+        Severity::Warning,
+        "A shell script uses a lowercase variable that is neither assigned nor an argument or option",
+    ),
+];
+
+/// A heading with everything up to the next heading.
+struct Section {
+    /// 1-based line of the heading.
+    line: usize,
+    level: usize,
+    /// The full command name like it's passed to mask.
+    command: String,
+    required_args: Vec<String>,
+    optional_args: Vec<String>,
+    description: String,
+    /// The last code block, which is the script.
+    script: Option<CodeBlock>,
+    /// The last code block that mask only runs on windows.
+    windows_script: Option<CodeBlock>,
+    /// 1-based line, name and whether the flags are set for every option.
+    options: Vec<(usize, String, bool)>,
+}
+
+struct CodeBlock {
+    /// 1-based line of the opening fence.
+    line: usize,
+    language: String,
+    source: String,
+}
+
+/// An identifier in a script and whether it's a shell variable reference
+/// like `$name` or `${name}`.
+struct Word<'a> {
+    /// 0-based line in the script.
+    line: usize,
+    text: &'a str,
+    is_reference: bool,
+}
+
+/// A script that mask only runs on windows, which mask-parser drops on other
+/// platforms.
+pub(crate) struct WindowsScript {
+    pub(crate) command: String,
+    /// The arguments and options that mask sets as variables.
+    pub(crate) variables: Vec<String>,
+    pub(crate) script: Script,
+}
+
+/// Finds the windows scripts in the order of the commands.
+pub(crate) fn windows_scripts(content: &str) -> Vec<WindowsScript> {
+    sections(content)
+        .into_iter()
+        .filter(|s| s.level > 1)
+        .filter_map(|section| {
+            let block = section.windows_script?;
+            let variables = section
+                .required_args
+                .into_iter()
+                .chain(section.optional_args)
+                .chain(section.options.into_iter().map(|(_, name, _)| name))
+                // mask adds the verbose flag to every command
+                .chain(["verbose".to_string()])
+                .collect();
+            let script = Script { executor: block.language, source: block.source };
+            Some(WindowsScript { command: section.command, variables, script })
+        })
+        .collect()
+}
+
+/// Checks the structure of the maskfile and returns findings with 1-based
+/// lines in the maskfile instead of a script.
+pub fn check(content: &str, config: &Config) -> Vec<Finding> {
+    let sections = sections(content);
+    let mut findings = vec![];
+    let mut defined: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut previous_level = 1;
+    for section in &sections {
+        if section.level > previous_level + 1 {
+            findings.push(finding(
+                section.line,
+                "MF005",
+                Severity::Warning,
+                format!(
+                    "heading of {} skips from level {previous_level} to {}",
+                    section.command, section.level
+                ),
+            ));
+        }
+        previous_level = section.level;
+        if section.level == 1 {
+            continue;
+        }
+
+        match defined.get(section.command.as_str()) {
+            Some(first) => findings.push(finding(
+                section.line,
+                "MF001",
+                Severity::Error,
+                format!("command {} is already defined in line {first}", section.command),
+            )),
+            None => {
+                defined.insert(&section.command, section.line);
+            }
+        }
+        if let Some(script) = section.script.as_ref().filter(|s| s.source.trim().is_empty()) {
+            findings.push(finding(
+                script.line,
+                "MF002",
+                Severity::Warning,
+                format!("command {} has an empty script", section.command),
+            ));
+        }
+        let description = words(&section.description);
+        for arg in &section.required_args {
+            if !description.iter().any(|w| w.text == arg) {
+                findings.push(finding(
+                    section.line,
+                    "MF003",
+                    Severity::Info,
+                    format!(
+                        "required argument {arg} of {} is not mentioned in its description",
+                        section.command
+                    ),
+                ));
+            }
+        }
+        for (line, name, has_flags) in &section.options {
+            if !has_flags {
+                findings.push(finding(
+                    *line,
+                    "MF004",
+                    Severity::Error,
+                    format!("option {name} of {} has no flags to set it", section.command),
+                ));
+            }
+        }
+        findings.extend(check_variables(section, config));
+    }
+    findings.sort_by_key(|f| f.line);
+    findings
+}
+
+/// Compares the arguments and options that mask sets as variables with the
+/// ones the scripts use, where the windows script counts as well.
+fn check_variables(section: &Section, config: &Config) -> Vec<Finding> {
+    let non_empty = |s: &&CodeBlock| !s.source.trim().is_empty();
+    let scripts: Vec<&CodeBlock> = [&section.script, &section.windows_script]
+        .into_iter()
+        .flatten()
+        .filter(non_empty)
+        .collect();
+    let used: Vec<Word> = scripts.iter().flat_map(|s| words(&s.source)).collect();
+    let mut findings = vec![];
+    if scripts.is_empty() {
+        return findings;
+    }
+    let args = section.required_args.iter().chain(&section.optional_args);
+    let declared = args.map(|a| (section.line, "argument", a));
+    let options = section.options.iter().map(|(line, name, _)| (*line, "option", name));
+    for (line, kind, name) in declared.chain(options) {
+        if !used.iter().any(|w| w.text == name) {
+            findings.push(finding(
+                line,
+                "MF006",
+                Severity::Warning,
+                format!("{kind} {name} of {} is never used in its script", section.command),
+            ));
+        }
+    }
+
+    let Some(script) = section.script.as_ref().filter(non_empty) else {
+        return findings;
+    };
+    let words = words(&script.source);
+    // other languages read the variables in too many different ways
+    let program = parse_executor(&script.language).map(|e| e.program).unwrap_or_default();
+    if !config.shell.undeclared_variables
+        || !matches!(program.as_str(), "sh" | "bash" | "dash" | "ksh" | "zsh")
+    {
+        return findings;
+    }
+    let mut known: BTreeSet<&str> = section
+        .required_args
+        .iter()
+        .chain(&section.optional_args)
+        .chain(section.options.iter().map(|(_, name, _)| name))
+        .chain(&config.shell.runtime_variables)
+        .map(String::as_str)
+        .collect();
+    // mask adds the verbose flag to every command
+    known.insert("verbose");
+    // anything that appears without a $ is most likely assigned, e.g. in a
+    // for loop or by read
+    known.extend(words.iter().filter(|w| !w.is_reference).map(|w| w.text));
+    for word in &words {
+        // uppercase variables are usually set in the environment
+        let lowercase = word.text.chars().all(|c| !c.is_ascii_uppercase()) && word.text != "_";
+        if word.is_reference && lowercase && known.insert(word.text) {
+            findings.push(finding(
+                script.line + 1 + word.line,
+                "MF007",
+                Severity::Warning,
+                format!(
+                    "{} is used in the script of {} but neither an argument nor an option",
+                    word.text, section.command
+                ),
+            ));
+        }
+    }
+    findings
+}
+
+/// Finds the identifiers in the script, skipping shell comments.
+fn words(source: &str) -> Vec<Word<'_>> {
+    let mut words = vec![];
+    for (line, text) in source.lines().enumerate() {
+        if text.trim_start().starts_with('#') {
+            continue;
+        }
+        let mut start = None;
+        for (i, c) in text.char_indices().chain([(text.len(), ' ')]) {
+            let is_word = c.is_ascii_alphanumeric() || c == '_';
+            match (start, is_word) {
+                (None, true) => start = Some(i),
+                (Some(s), false) => {
+                    let word = &text[s..i];
+                    let prefix = text[..s].trim_end_matches(['#', '!']);
+                    let is_reference = prefix.ends_with('$') || prefix.ends_with("${");
+                    if !word.starts_with(|c: char| c.is_ascii_digit()) {
+                        words.push(Word { line, text: word, is_reference });
+                    }
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+    }
+    words
+}
+
+fn finding(line: usize, rule: &str, severity: Severity, message: String) -> Finding {
+    Finding { line: Some(line), column: None, rule: Some(rule.to_string()), severity, message }
+}
+
+/// Splits the maskfile at its headings like mask-parser does, which stops at
+/// the second title.
+fn sections(content: &str) -> Vec<Section> {
+    let mut sections: Vec<Section> = vec![];
+    let mut open_fence: Option<(char, CodeBlock)> = None;
+    let mut in_options = false;
+    for (i, raw_line) in content.lines().enumerate() {
+        let (number, indented) = (i + 1, raw_line.starts_with([' ', '\t']));
+        let line = raw_line.trim();
+        if let Some((fence, block)) = open_fence.as_mut() {
+            if !closes_fence(line, *fence) {
+                block.source.push_str(raw_line);
+                block.source.push('\n');
+                continue;
+            }
+            if let (Some((_, block)), Some(section)) = (open_fence.take(), sections.last_mut()) {
+                section.add_block(block);
+            }
+            continue;
+        }
+        if let Some(fence) = opening_fence(line) {
+            let language = line.trim_start_matches(fence).trim();
+            let block =
+                CodeBlock { line: number, language: language.to_string(), source: String::new() };
+            open_fence = Some((fence, block));
+            in_options = false;
+            continue;
+        }
+        if let Some((level, text)) = heading(line) {
+            if level == 1 && sections.iter().any(|s| s.level > 1) {
+                break;
+            }
+            sections.push(section(&sections, number, level, text));
+            in_options = false;
+            continue;
+        }
+        let Some(section) = sections.last_mut() else {
+            continue;
+        };
+        if let Some(quote) = line.strip_prefix('>') {
+            section.description.push_str(quote.trim());
+            section.description.push(' ');
+        } else if matches!(line, "OPTIONS" | "**OPTIONS**" | "__OPTIONS__") {
+            in_options = true;
+        } else if in_options && !line.is_empty() {
+            let item = line.strip_prefix(['*', '-', '+']).map(str::trim);
+            match (item, indented) {
+                (Some(name), false) => section.options.push((number, name.to_string(), false)),
+                (Some(config), true) if config.starts_with("flags:") => {
+                    let flags = config.trim_start_matches("flags:").trim();
+                    if let Some(option) = section.options.last_mut() {
+                        option.2 |= !flags.is_empty();
+                    }
+                }
+                (_, true) => {}
+                (None, false) => in_options = false,
+            }
+        }
+    }
+    // an unclosed code block runs until the end of the file
+    if let (Some((_, block)), Some(section)) = (open_fence, sections.last_mut()) {
+        section.add_block(block);
+    }
+    sections
+}
+
+fn section(previous: &[Section], line: usize, level: usize, text: &str) -> Section {
+    let name_end = text.find(['(', '[']).unwrap_or(text.len());
+    let args = |open, close| {
+        text[name_end..]
+            .split(open)
+            .skip(1)
+            .filter_map(|arg| arg.split_once(close))
+            .map(|(arg, _)| arg.trim().to_string())
+            .filter(|arg| !arg.is_empty())
+            .collect()
+    };
+    let parent = previous.iter().rev().find(|s| s.level < level && s.level > 1);
+    let command = command_name(parent.map(|p| p.command.as_str()), text);
+    Section {
+        line,
+        level,
+        command,
+        required_args: args('(', ')'),
+        optional_args: args('[', ']'),
+        description: String::new(),
+        script: None,
+        windows_script: None,
+        options: vec![],
+    }
+}
+
+impl Section {
+    /// Like mask, the last block of the section is the script.
+    fn add_block(&mut self, block: CodeBlock) {
+        match is_windows_only(&block.language) {
+            true => self.windows_script = Some(block),
+            false => self.script = Some(block),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(maskfile: &str) -> Vec<(Option<usize>, String)> {
+        check(maskfile, &Config::default()).into_iter().map(|f| (f.line, f.rule.unwrap())).collect()
+    }
+
+    #[test]
+    fn test_check() {
+        let maskfile = "# Tasks
+
+## build (target)
+
+> builds the project
+
+**OPTIONS**
+
+* release
+  * flags: --release
+* profile
+  * type: string
+
+```bash
+cargo build --target \"$target\" ${release:+--release} $profile
+```
+
+#### build docs
+
+```sh
+```
+
+## build
+
+```bash
+echo
+```
+";
+        assert_eq!(
+            rules(maskfile),
+            [(3, "MF003"), (11, "MF004"), (18, "MF005"), (20, "MF002"), (23, "MF001")]
+                .map(|(line, rule)| (Some(line), rule.to_string()))
+        );
+    }
+
+    #[test]
+    fn test_check_description() {
+        let maskfile = "## build (target) (env)
+
+> builds the target_dir for the env
+
+```bash
+make \"$target\" \"$env\"
+```
+";
+        assert_eq!(rules(maskfile), [(Some(1), "MF003".to_string())]);
+    }
+
+    #[test]
+    fn test_check_variables() {
+        let maskfile = "# Tasks
+
+## deploy (env) [region]
+
+> deploys to the env
+
+**OPTIONS**
+
+* dry_run
+  * flags: --dry-run
+
+```bash
+for host in $(hosts \"$env\"); do
+    # $commented is ignored
+    echo \"$host $target ${#target} $HOME $MASK\"
+done
+```
+";
+        assert_eq!(
+            rules(maskfile),
+            [(3, "MF006"), (9, "MF006")].map(|(line, rule)| (Some(line), rule.to_string()))
+        );
+        let mut config = Config::default();
+        config.shell.undeclared_variables = true;
+        let rules: Vec<(Option<usize>, String)> =
+            check(maskfile, &config).into_iter().map(|f| (f.line, f.rule.unwrap())).collect();
+        assert_eq!(
+            rules,
+            [(3, "MF006"), (9, "MF006"), (15, "MF007")]
+                .map(|(line, rule)| (Some(line), rule.to_string()))
+        );
+    }
+
+    #[test]
+    fn test_check_windows_variables() {
+        let maskfile = "## build (target) (profile)
+
+> builds the target with the profile
+
+```bash
+make \"$target\"
+```
+
+```powershell
+msbuild $env:target /p:Configuration=$env:profile
+```
+";
+        assert_eq!(rules(maskfile), []);
+    }
+
+    #[test]
+    fn test_check_valid() {
+        let maskfile = "# Tasks
+
+## deploy (env)
+
+> deploys to the env
+
+```bash
+echo \"$env\"
+```
+
+### deploy check
+
+```python
+print(\"ok\")
+```
+
+```powershell
+```
+";
+        assert_eq!(rules(maskfile), vec![]);
+    }
+}
@@ -0,0 +1,233 @@
+use std::{env, process::Command};
+
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout},
+    style::{Modifier, Style, Stylize},
+    text::{Line, Text},
+    widgets::{Block, List, ListItem, ListState, Paragraph, Wrap},
+    DefaultTerminal, Frame,
+};
+
+use masklint::{
+    findings::Severity,
+    report::{CommandReport, CommandStatus},
+    STDIN_LABEL,
+};
+
+const HELP: &str = " ↑↓ command  enter expand  n/N next/previous finding  PgUp/PgDn scroll  e open in $EDITOR  q quit ";
+
+/// Opens a terminal interface that lists the commands with findings, shows
+/// their linter output and opens the maskfile at a finding in the editor.
+pub fn browse(reports: &[CommandReport]) -> anyhow::Result<()> {
+    let items: Vec<Item> = reports
+        .iter()
+        .filter(|r| r.status == CommandStatus::Failed || !r.findings.is_empty())
+        .map(|report| Item {
+            report,
+            // the first line if the script couldn't be found reliably
+            lines: report.findings.iter().map(|f| report.maskfile_line(f).unwrap_or(1)).collect(),
+        })
+        .collect();
+    if items.is_empty() {
+        println!("No findings to browse.");
+        return Ok(());
+    }
+
+    let mut app = App {
+        items,
+        commands: ListState::default(),
+        expanded: false,
+        finding: 0,
+        scroll: 0,
+        details_height: 0,
+    };
+    app.commands.select(Some(0));
+    let mut terminal = ratatui::init();
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+struct Item<'a> {
+    report: &'a CommandReport,
+    /// The 1-based line in the maskfile of every finding.
+    lines: Vec<usize>,
+}
+
+struct App<'a> {
+    items: Vec<Item<'a>>,
+    commands: ListState,
+    /// Whether the findings of the selected command can be stepped through.
+    expanded: bool,
+    /// The selected finding of the selected command.
+    finding: usize,
+    /// The first visible line of the details.
+    scroll: usize,
+    /// How many lines of the details fit on the screen, as of the last draw.
+    details_height: usize,
+}
+
+impl App<'_> {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> anyhow::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') => return Ok(()),
+                KeyCode::Esc if !self.expanded => return Ok(()),
+                KeyCode::Esc | KeyCode::Left | KeyCode::Char('h') => self.expanded = false,
+                KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => self.expanded = true,
+                KeyCode::Down | KeyCode::Char('j') => self.select(self.selected() + 1),
+                KeyCode::Up | KeyCode::Char('k') => self.select(self.selected().saturating_sub(1)),
+                KeyCode::Char('n') => self.step(true),
+                KeyCode::Char('N') | KeyCode::Char('p') => self.step(false),
+                KeyCode::PageDown => self.scroll += self.details_height.max(1),
+                KeyCode::PageUp => {
+                    self.scroll = self.scroll.saturating_sub(self.details_height.max(1))
+                }
+                KeyCode::Char('e') => {
+                    // the editor needs the terminal for itself
+                    ratatui::restore();
+                    let result = self.open_editor();
+                    *terminal = ratatui::init();
+                    result?;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn selected(&self) -> usize {
+        self.commands.selected().unwrap_or_default()
+    }
+
+    fn select(&mut self, index: usize) {
+        self.commands.select(Some(index.min(self.items.len() - 1)));
+        self.finding = 0;
+        self.scroll = 0;
+    }
+
+    /// Moves to the next or previous finding, continuing with the next or
+    /// previous command that has findings.
+    fn step(&mut self, forward: bool) {
+        // the first step only highlights the current finding
+        if !self.expanded && !self.items[self.selected()].report.findings.is_empty() {
+            self.expanded = true;
+            return;
+        }
+        let positions: Vec<(usize, usize)> = self
+            .items
+            .iter()
+            .enumerate()
+            .flat_map(|(i, item)| (0..item.report.findings.len()).map(move |j| (i, j)))
+            .collect();
+        let current = (self.selected(), self.finding);
+        let next = match forward {
+            true => positions.iter().find(|p| **p > current),
+            false => positions.iter().rev().find(|p| **p < current),
+        };
+        if let Some(&(i, j)) = next {
+            if i != self.selected() {
+                self.scroll = 0;
+            }
+            self.commands.select(Some(i));
+            self.finding = j;
+            self.expanded = true;
+            // keep the selected finding in view, every finding is one line
+            if self.finding < self.scroll {
+                self.scroll = self.finding;
+            } else if self.finding >= self.scroll + self.details_height {
+                self.scroll = self.finding + 1 - self.details_height;
+            }
+        }
+    }
+
+    fn open_editor(&self) -> anyhow::Result<()> {
+        let item = &self.items[self.selected()];
+        let maskfile = &item.report.maskfile;
+        if maskfile == STDIN_LABEL {
+            return Ok(());
+        }
+        let line = item.lines.get(self.finding).copied().unwrap_or(1);
+        let editor = env::var("VISUAL").or_else(|_| env::var("EDITOR")).unwrap_or("vi".into());
+        // the editor may come with arguments, e.g. "code --wait"
+        let mut words = editor.split_whitespace();
+        let executable = words.next().unwrap_or("vi");
+        // +line is understood by vi, nano, emacs, helix and most others
+        Command::new(executable).args(words).arg(format!("+{line}")).arg(maskfile).status()?;
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, help] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let [list, details] =
+            Layout::horizontal([Constraint::Percentage(35), Constraint::Percentage(65)])
+                .areas(main);
+
+        let labeled = self.items.iter().any(|i| i.report.maskfile != self.items[0].report.maskfile);
+        let commands: Vec<ListItem> = self
+            .items
+            .iter()
+            .map(|item| {
+                let report = item.report;
+                let name = match labeled {
+                    true => format!("{}: {}", report.maskfile, report.command),
+                    false => report.command.clone(),
+                };
+                let count = report.findings.len();
+                let plural = if count == 1 { "" } else { "s" };
+                let line =
+                    Line::from(vec![name.into(), format!(" ({count} finding{plural})").dim()]);
+                ListItem::new(match report.status {
+                    CommandStatus::Failed => line.red(),
+                    _ => line,
+                })
+            })
+            .collect();
+        let commands = List::new(commands)
+            .block(Block::bordered().title(" Commands "))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(commands, list, &mut self.commands);
+
+        let item = &self.items[self.selected()];
+        let mut text = Text::default();
+        for (j, (finding, line)) in item.report.findings.iter().zip(&item.lines).enumerate() {
+            let severity = match finding.severity {
+                Severity::Error => finding.severity.to_string().red(),
+                Severity::Warning => finding.severity.to_string().yellow(),
+                Severity::Info | Severity::Style => finding.severity.to_string().blue(),
+            };
+            let rule = finding.rule.as_deref().unwrap_or_default();
+            let mut line = Line::from(vec![
+                format!("line {line:<5}").dim(),
+                severity,
+                format!(" {rule} {}", finding.message).into(),
+            ]);
+            if self.expanded && j == self.finding {
+                line = line.add_modifier(Modifier::REVERSED);
+            }
+            text.push_line(line);
+        }
+        if self.expanded {
+            text.push_line("");
+            text.extend(Text::raw(item.report.output.as_str()));
+        }
+        // without the borders
+        self.details_height = usize::from(details.height.saturating_sub(2));
+        self.scroll = self.scroll.min(text.lines.len().saturating_sub(1));
+        let title = format!(" {} ({}) ", item.report.command, item.report.linters.join(", "));
+        let details_widget = Paragraph::new(text)
+            .block(Block::bordered().title(title))
+            .wrap(Wrap { trim: false })
+            .scroll((u16::try_from(self.scroll).unwrap_or(u16::MAX), 0));
+        frame.render_widget(details_widget, details);
+        frame.render_widget(Line::from(HELP).dim(), help);
+    }
+}
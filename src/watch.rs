@@ -0,0 +1,108 @@
+use notify::{RecursiveMode, Watcher};
+use owo_colors::{Stream, Style};
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+
+use crate::findings_summary;
+use masklint::{color::paint, process_maskfile, ProcessCommandContext, Summary};
+
+// editors tend to write a file in multiple steps (truncate, write, rename),
+// so events are collected for a short while before re-running the linters
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Watches the maskfiles for changes and re-runs the linters for the changed
+/// ones on every save. This only returns if the watcher itself fails.
+pub fn watch(maskfiles: &[PathBuf], context: &ProcessCommandContext) -> anyhow::Result<()> {
+    let maskfiles =
+        maskfiles.iter().map(|p| p.canonicalize()).collect::<Result<Vec<PathBuf>, _>>()?;
+    // the parent dirs are watched instead of the files themselves since a lot
+    // of editors replace the file on save which would end the watch
+    let watch_dirs: BTreeSet<&Path> =
+        maskfiles.iter().map(|p| p.parent().unwrap_or(Path::new("."))).collect();
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    for dir in watch_dirs {
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    }
+
+    run_once(&maskfiles, &maskfiles, context);
+    print_waiting(&maskfiles);
+
+    loop {
+        let mut changed = BTreeSet::new();
+        collect_changed(rx.recv()?, &maskfiles, &mut changed);
+        if changed.is_empty() {
+            continue;
+        }
+        while let Ok(event) = rx.recv_timeout(DEBOUNCE) {
+            collect_changed(event, &maskfiles, &mut changed);
+        }
+
+        // clear the screen and move the cursor to the top left
+        print!("\x1B[2J\x1B[1;1H");
+        run_once(&changed.into_iter().collect::<Vec<PathBuf>>(), &maskfiles, context);
+        print_waiting(&maskfiles);
+    }
+}
+
+fn run_once(changed: &[PathBuf], maskfiles: &[PathBuf], context: &ProcessCommandContext) {
+    let mut summary = Summary::default();
+    for maskfile_path in changed {
+        match lint_maskfile(maskfile_path, maskfiles.len() > 1, context) {
+            Ok(s) => summary.add(s),
+            Err(e) => {
+                let error = format!("{}: {e}", maskfile_path.display());
+                eprintln!("{}", paint(error, Stream::Stderr, Style::new().bold().red()));
+            }
+        }
+    }
+    summary.print_missing_linters();
+    if summary.findings > 0 {
+        let failures = findings_summary(summary.findings);
+        println!("{}", paint(failures, Stream::Stdout, Style::new().bold().red()));
+    } else {
+        println!("{}", paint("No lint failures.", Stream::Stdout, Style::new().bold().green()));
+    }
+}
+
+fn lint_maskfile(
+    maskfile_path: &Path,
+    labeled: bool,
+    context: &ProcessCommandContext,
+) -> anyhow::Result<Summary> {
+    // every run gets a fresh dir since the script files are never overwritten
+    let tmp_dir = tempfile::tempdir()?;
+    let mut context =
+        ProcessCommandContext { out_dir: tmp_dir.path().to_path_buf(), ..context.clone() };
+    if labeled {
+        let section = format!("==> {}", maskfile_path.display());
+        println!("{}", paint(section, Stream::Stdout, Style::new().bold()));
+        context = context.for_maskfile(maskfile_path)?;
+    }
+    process_maskfile(maskfile_path, &context)
+}
+
+fn collect_changed(
+    event: notify::Result<notify::Event>,
+    maskfiles: &[PathBuf],
+    changed: &mut BTreeSet<PathBuf>,
+) {
+    let Ok(event) = event else {
+        return;
+    };
+    if !(event.kind.is_modify() || event.kind.is_create()) {
+        return;
+    }
+    changed.extend(maskfiles.iter().filter(|m| event.paths.contains(m)).cloned());
+}
+
+fn print_waiting(maskfiles: &[PathBuf]) {
+    let paths = maskfiles.iter().map(|p| p.display().to_string()).collect::<Vec<String>>();
+    let waiting = format!("Watching {} for changes...", paths.join(", "));
+    println!("{}", paint(waiting, Stream::Stdout, Style::new().dimmed()));
+}